@@ -0,0 +1,133 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use crunch::{Item, Packer, Rect, Rotation, PO2_SHAPES};
+
+/// Deterministically generate `count` items between 1 and `max_size` in size.
+fn synthetic_items(count: usize, max_size: usize, rot: Rotation) -> Vec<Item<usize>> {
+    let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed as usize
+    };
+    (0..count)
+        .map(|i| Item::new(i, 1 + next() % max_size, 1 + next() % max_size, rot))
+        .collect()
+}
+
+fn pack_indexed(c: &mut Criterion) {
+    let items = synthetic_items(5000, 32, Rotation::None);
+    let rect = Rect::of_size(1024, 1024);
+
+    let mut group = c.benchmark_group("repeated_inserts");
+    group.sample_size(10);
+    group.bench_function("tree", |b| {
+        let mut packer = Packer::with_items(items.clone());
+        b.iter(|| black_box(packer.pack(rect)).is_ok())
+    });
+    group.bench_function("index", |b| {
+        let mut packer = Packer::with_items(items.clone()).with_index(true);
+        b.iter(|| black_box(packer.pack(rect)).is_ok())
+    });
+    group.finish();
+}
+
+fn pack_rotation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rotation");
+    group.sample_size(10);
+    for rot in [Rotation::None, Rotation::Allowed] {
+        let items = synthetic_items(1000, 64, rot);
+        group.bench_function(format!("{:?}", rot), |b| {
+            let mut packer = Packer::with_items(items.clone());
+            b.iter(|| black_box(packer.pack_into_po2(4096)).is_ok())
+        });
+    }
+    group.finish();
+}
+
+fn pack_rotation_override(c: &mut Criterion) {
+    let items = synthetic_items(2000, 32, Rotation::Allowed);
+    let rect = Rect::of_size(1024, 1024);
+
+    let mut group = c.benchmark_group("rotation_override");
+    group.sample_size(10);
+    group.bench_function("allowed", |b| {
+        let mut packer = Packer::with_items(items.clone());
+        b.iter(|| black_box(packer.pack(rect)).is_ok())
+    });
+    group.bench_function("override_none", |b| {
+        let packer = Packer::with_items(items.clone());
+        let mut packer = packer.with_rotation_override(Some(Rotation::None));
+        b.iter(|| black_box(packer.pack(rect)).is_ok())
+    });
+    group.finish();
+}
+
+fn pack_po2_search(c: &mut Criterion) {
+    let items = synthetic_items(2000, 32, Rotation::Allowed);
+
+    let mut group = c.benchmark_group("po2_search");
+    group.sample_size(10);
+    group.bench_function("pack_into_po2", |b| {
+        let mut packer = Packer::with_items(items.clone());
+        b.iter(|| black_box(packer.pack_into_po2(4096)).is_ok())
+    });
+    // the same containers, smallest first, but sorting the items again for each
+    group.bench_function("pack_each_size", |b| {
+        let mut packer = Packer::with_items(items.clone());
+        b.iter(|| {
+            let sizes = (1..=12).map(|p| 1 << p);
+            let mut rects =
+                sizes.flat_map(|s| PO2_SHAPES.map(|(w, h)| Rect::of_size(s * w, s * h)));
+            black_box(rects.find(|&rect| packer.pack(rect).is_ok()))
+        })
+    });
+    group.finish();
+}
+
+fn pack_node_reserve(c: &mut Criterion) {
+    // small rotatable items split the free space up far more than usual
+    let items = synthetic_items(2000, 8, Rotation::Allowed);
+    let rect = Rect::of_size(256, 256);
+
+    let mut group = c.benchmark_group("node_reserve");
+    group.sample_size(10);
+    for factor in [0, 2, 8] {
+        let packer = Packer::with_items(items.clone()).with_node_reserve_factor(factor);
+        group.bench_function(format!("factor_{}", factor), |b| {
+            b.iter(|| black_box(packer.pack_cloned(rect)).is_ok())
+        });
+    }
+    group.finish();
+}
+
+fn pack_synthetic_1k(c: &mut Criterion) {
+    let items = synthetic_items(1000, 32, Rotation::None);
+    c.bench_function("pack_synthetic_1k", |b| {
+        let mut packer = Packer::with_items(items.clone());
+        b.iter(|| black_box(packer.pack_into_po2(4096)).is_ok())
+    });
+}
+
+fn pack_synthetic_10k(c: &mut Criterion) {
+    let items = synthetic_items(10_000, 32, Rotation::None);
+    let mut group = c.benchmark_group("synthetic");
+    group.sample_size(10);
+    group.bench_function("pack_synthetic_10k", |b| {
+        let mut packer = Packer::with_items(items.clone());
+        b.iter(|| black_box(packer.pack_into_po2(4096)).is_ok())
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    pack_indexed,
+    pack_rotation,
+    pack_rotation_override,
+    pack_node_reserve,
+    pack_po2_search,
+    pack_synthetic_1k,
+    pack_synthetic_10k
+);
+criterion_main!(benches);
@@ -1,5 +1,5 @@
 extern crate image;
-use crunch::{Item, PackedItem, PackedItems, Rotation};
+use crunch::{Item, PackedItem, PackedItems};
 use image::{GenericImage, Rgba, RgbaImage};
 
 fn main() {
@@ -38,7 +38,7 @@ fn main() {
         let img = image::open(file).unwrap().to_rgba8();
         let (w, h) = (img.width() as usize, img.height() as usize);
         println!("\tloaded: `{}` ({} x {})", file, w, h);
-        Item::new(img, w, h, Rotation::None)
+        Item::no_rotation(img, w, h)
     });
 
     println!("packing {} images...", items.len());
@@ -52,7 +52,7 @@ fn main() {
             let mut atlas = RgbaImage::from_pixel(w as u32, h as u32, Rgba([0, 0, 0, 0]));
 
             // Copy all the packed images onto the target atlas
-            for PackedItem { data, rect } in items {
+            for PackedItem { data, rect, .. } in items {
                 atlas
                     .copy_from(&data, rect.x as u32, rect.y as u32)
                     .unwrap();
@@ -0,0 +1,87 @@
+use crate::PackedItem;
+use std::fmt;
+
+/// A problem with the items queued in a [`Packer`](crate::Packer), found by
+/// [`Packer::validate`](crate::Packer::validate), or with the items it packed, found by
+/// [`Packer::verify_last_pack`](crate::Packer::verify_last_pack).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The item at `index` has a width, height, or scale of 0.
+    ZeroSized { index: usize },
+
+    /// The item at `index` is so large its area does not fit in a `usize`.
+    TooLarge { index: usize },
+
+    /// The items at `first` and `second` refer to the same data.
+    DuplicateData { first: usize, second: usize },
+
+    /// The packed item at `index` lies outside of the container.
+    OutOfBounds { index: usize },
+
+    /// The packed items at `first` and `second` overlap each other.
+    Overlapping { first: usize, second: usize },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ZeroSized { index } => {
+                write!(f, "item {} has a zero width, height, or scale", index)
+            }
+            Self::TooLarge { index } => write!(f, "item {} is too large to pack", index),
+            Self::DuplicateData { first, second } => {
+                write!(f, "items {} and {} refer to the same data", first, second)
+            }
+            Self::OutOfBounds { index } => {
+                write!(f, "packed item {} lies outside the container", index)
+            }
+            Self::Overlapping { first, second } => {
+                write!(f, "packed items {} and {} overlap", first, second)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Why [`Packer::try_pack`](crate::Packer::try_pack) failed to pack every item.
+#[derive(Debug)]
+pub enum PackError<T> {
+    /// An item didn't fit into the container. Holds the items that were packed.
+    DoesNotFit(Vec<PackedItem<T>>),
+
+    /// The free space split up into `nodes` pieces, more than the `limit` set by
+    /// [`Packer::with_max_fragmentation`](crate::Packer::with_max_fragmentation).
+    /// Holds the items that were packed before giving up.
+    TooFragmented {
+        packed: Vec<PackedItem<T>>,
+        nodes: usize,
+        limit: usize,
+    },
+}
+
+impl<T> PackError<T> {
+    /// The items that were packed before failing.
+    pub fn into_packed(self) -> Vec<PackedItem<T>> {
+        match self {
+            Self::DoesNotFit(packed) | Self::TooFragmented { packed, .. } => packed,
+        }
+    }
+}
+
+impl<T> fmt::Display for PackError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DoesNotFit(packed) => {
+                write!(f, "only {} items fit into the container", packed.len())
+            }
+            Self::TooFragmented { nodes, limit, .. } => write!(
+                f,
+                "free space split into {} pieces, more than the limit of {}",
+                nodes, limit
+            ),
+        }
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for PackError<T> {}
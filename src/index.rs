@@ -0,0 +1,101 @@
+use crate::tree::{Node, Score};
+use crate::Rect;
+
+/// The number of size buckets per axis, one for each possible `log2` of a `usize`.
+const BUCKETS: usize = usize::BITS as usize + 1;
+
+/// An index of the packing tree's free leaf nodes, bucketed by the `log2` of their
+/// width and height, so searches can skip straight past leaves that are too small.
+pub(crate) struct FreeIndex {
+    buckets: Vec<Vec<usize>>,
+    slots: Vec<(usize, usize)>,
+    max_bucket: (usize, usize),
+}
+
+impl FreeIndex {
+    pub(crate) const fn new() -> Self {
+        Self {
+            buckets: Vec::new(),
+            slots: Vec::new(),
+            max_bucket: (0, 0),
+        }
+    }
+
+    #[inline]
+    fn bucket_of(size: usize) -> usize {
+        (usize::BITS - size.leading_zeros()) as usize
+    }
+
+    /// The smallest size a rect in the bucket could have.
+    #[inline]
+    fn bucket_min(bucket: usize) -> usize {
+        match bucket {
+            0 => 0,
+            b => 1 << (b - 1),
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        if self.buckets.is_empty() {
+            self.buckets.resize_with(BUCKETS * BUCKETS, Vec::new);
+        }
+        for bucket in &mut self.buckets {
+            bucket.clear();
+        }
+        self.slots.clear();
+        self.max_bucket = (0, 0);
+    }
+
+    /// Add the free leaf `node` with the rectangle `rect` to the index.
+    pub(crate) fn insert(&mut self, node: usize, rect: &Rect) {
+        let (bx, by) = (Self::bucket_of(rect.w), Self::bucket_of(rect.h));
+        let bucket = bx * BUCKETS + by;
+        if self.slots.len() <= node {
+            self.slots.resize(node + 1, (usize::MAX, usize::MAX));
+        }
+        self.slots[node] = (bucket, self.buckets[bucket].len());
+        self.buckets[bucket].push(node);
+        self.max_bucket = (self.max_bucket.0.max(bx), self.max_bucket.1.max(by));
+    }
+
+    /// Remove `node` from the index, if it is in it.
+    pub(crate) fn remove(&mut self, node: usize) {
+        let (bucket, pos) = match self.slots.get(node) {
+            Some(&(bucket, pos)) if bucket != usize::MAX => (bucket, pos),
+            _ => return,
+        };
+        self.buckets[bucket].swap_remove(pos);
+        if let Some(&moved) = self.buckets[bucket].get(pos) {
+            self.slots[moved].1 = pos;
+        }
+        self.slots[node] = (usize::MAX, usize::MAX);
+    }
+
+    /// Find the indexed leaf that best fits a `w` x `h` rect, along with its score,
+    /// and whether any other leaf tied with it for the best score.
+    pub(crate) fn find_best(&self, nodes: &[Node], w: usize, h: usize) -> (usize, Score, bool) {
+        let area = w.saturating_mul(h);
+        let mut best = (usize::MAX, Score::worst(), false);
+        for bx in Self::bucket_of(w)..=self.max_bucket.0 {
+            for by in Self::bucket_of(h)..=self.max_bucket.1 {
+                // every rect in this bucket, or taller buckets, wastes at least this much
+                let min_area = Self::bucket_min(bx).saturating_mul(Self::bucket_min(by));
+                if min_area.saturating_sub(area) > best.1.area_fit {
+                    break;
+                }
+                for &node in &self.buckets[bx * BUCKETS + by] {
+                    let rect = &nodes[node].rect;
+                    if w <= rect.w && h <= rect.h {
+                        let score = Score::new(rect, w, h);
+                        if score.better_than(&best.1) {
+                            best = (node, score, false);
+                        } else if !best.1.better_than(&score) {
+                            best.2 = true;
+                        }
+                    }
+                }
+            }
+        }
+        best
+    }
+}
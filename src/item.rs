@@ -1,17 +1,49 @@
 use crate::Rect;
+use std::collections::HashMap;
+use std::hash::Hash;
 
 /// Rotation setting for packing rectangles.
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Rotation {
     /// The item may not be rotated.
     None,
 
     /// The item may be rotated 90° to fit better.
+    ///
+    /// This is for items whose size is their upright orientation, so if
+    /// [`rotated`](PackedItem::rotated), they need turning back when they're drawn.
     Allowed,
+
+    /// The item has no upright orientation: its `w` x `h` size is only one of its two
+    /// equally good orientations, and the packer picks whichever fits better.
+    ///
+    /// This packs exactly like [`Allowed`](Rotation::Allowed) does, so it's for items
+    /// that were already rotated to one side (like content that was turned before it
+    /// was handed to the packer), where [`rotated`](PackedItem::rotated) just reports
+    /// which of the two orientations was picked, rather than that anything needs
+    /// turning back.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation, Rect, pack};
+    /// // each item only fits into the 4 x 10 container stood up on its side
+    /// let rect = Rect::of_size(4, 10);
+    /// for rot in [Rotation::None, Rotation::Allowed, Rotation::Auto] {
+    ///     let packed = pack(rect, [Item::new(rot, 10, 4, rot)]);
+    ///     match rot {
+    ///         Rotation::None => assert!(packed.is_err()),
+    ///         _ => assert!(packed.unwrap()[0].rotated),
+    ///     }
+    ///
+    ///     // items that fit as they are aren't rotated, whatever their setting
+    ///     let packed = pack(rect, [Item::new(rot, 4, 10, rot)]).unwrap();
+    ///     assert!(!packed[0].rotated);
+    /// }
+    /// ```
+    Auto,
 }
 
 /// An item to be packed by `Packer`.
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Item<T> {
     /// Data associated with the item (for example, an ID or a
     /// reference to an image).
@@ -31,24 +63,423 @@ pub struct Item<T> {
     ///
     /// If an item is square, it will never be rotated.
     pub rot: Rotation,
+
+    /// If set, this is used as the item's [`sort_priority`](Item::sort_priority)
+    /// instead of the one computed from its size.
+    ///
+    /// Items are packed highest priority first, so this can force specific items
+    /// to be placed early (or late) regardless of their size.
+    pub sort_override: Option<usize>,
+
+    /// The item's [`sort_priority`](Item::sort_priority) is multiplied by this (`1.0`
+    /// by default), so more important items can be packed before others their size.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rect, pack};
+    /// let a = Item::no_rotation('A', 4, 4);
+    /// let mut b = Item::no_rotation('B', 4, 4);
+    ///
+    /// // equal items are packed in order...
+    /// let packed = pack(Rect::of_size(8, 4), [a.clone(), b.clone()]).unwrap();
+    /// assert_eq!(packed[0].data, 'A');
+    ///
+    /// // ...unless one is weighted higher
+    /// b.weight = 2.0;
+    /// let packed = pack(Rect::of_size(8, 4), [a, b]).unwrap();
+    /// assert_eq!(packed[0].data, 'B');
+    /// assert_eq!(packed[0].rect, Rect::new(0, 0, 4, 4));
+    /// ```
+    pub weight: f32,
+
+    /// The item is packed at `scale` times its size (`1` by default), for packing
+    /// the same items at several resolutions while keeping track of their logical size.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rect, pack};
+    /// let mut item = Item::no_rotation('A', 4, 3);
+    /// item.scale = 2;
+    ///
+    /// let packed = pack(Rect::of_size(16, 16), [item]).unwrap();
+    /// assert_eq!(packed[0].rect.area(), 4 * 4 * 3);
+    /// assert_eq!(packed[0].scale, 2);
+    /// assert_eq!(packed[0].logical_size(), (4, 3));
+    /// ```
+    pub scale: u32,
+
+    /// If `true`, the item is only packed if there's space left after all the
+    /// required (non-optional) items are packed, and packing doesn't fail if it
+    /// doesn't fit. Optional items that don't fit can be found with
+    /// [`Packer::unpacked`](crate::Packer::unpacked).
+    ///
+    /// ```
+    /// # use crunch::{Item, Packer, Rect};
+    /// let mut items: Vec<Item<usize>> = (0..2).map(|i| Item::no_rotation(i, 8, 16)).collect();
+    ///
+    /// // the fillers are larger, but still get packed after the required items
+    /// for i in 2..6 {
+    ///     let mut filler = Item::no_rotation(i, 16, 4);
+    ///     filler.optional = true;
+    ///     items.push(filler);
+    /// }
+    ///
+    /// let mut packer = Packer::with_items(items);
+    /// let packed = packer.pack(Rect::of_size(16, 24)).unwrap();
+    ///
+    /// // both required items fit, and then 2 of the 4 fillers
+    /// assert_eq!(packed.iter().filter(|i| !i.optional).count(), 2);
+    /// assert_eq!(packed.iter().filter(|i| i.optional).count(), 2);
+    /// assert_eq!(packer.unpacked().count(), 2);
+    /// ```
+    pub optional: bool,
+
+    /// A visual rotation in degrees (such as `0`, `90`, `180` or `270`) for your own
+    /// use, which the packer ignores and copies into
+    /// [`PackedItem::user_rotation`] as is.
+    ///
+    /// Whether the packer rotated the item 90° to fit it is separate, see
+    /// [`PackedItem::rotated`].
+    ///
+    /// ```
+    /// # use crunch::{Item, Rect, pack};
+    /// let mut item = Item::no_rotation('A', 8, 4);
+    /// item.user_rotation = 270;
+    ///
+    /// let packed = pack(Rect::of_size(16, 16), [item]).unwrap();
+    /// assert_eq!(packed[0].user_rotation, 270);
+    /// assert!(!packed[0].rotated);
+    /// ```
+    pub user_rotation: u16,
+
+    /// If `true`, the item is never rotated, even if its [`rot`](Item::rot) (or the
+    /// packer's [rotation override](crate::Packer::with_rotation_override)) allows
+    /// it, for items whose aspect ratio must be kept as is.
+    ///
+    /// ```
+    /// # use crunch::{Item, Packer, Rect};
+    /// let mut locked = Item::rotatable('L', 4, 16);
+    /// locked.lock_aspect = true;
+    /// let free = Item::rotatable('F', 4, 16);
+    ///
+    /// // both items need rotating to fit, but only the unlocked one can be
+    /// let mut packer = Packer::with_items([free, locked]);
+    /// let packed = packer.pack(Rect::of_size(16, 8)).unwrap_err();
+    /// assert_eq!(packed.len(), 1);
+    /// assert_eq!(packed[0].data, 'F');
+    /// assert!(packed[0].rotated);
+    ///
+    /// // with room for the locked item upright, it packs without rotating
+    /// let packed = packer.pack(Rect::of_size(32, 16)).unwrap();
+    /// let locked = packed.iter().find(|p| p.data == 'L').unwrap();
+    /// assert_eq!((locked.rect.w, locked.rect.h), (4, 16));
+    /// ```
+    pub lock_aspect: bool,
+
+    /// If set, the item is kept together with all other items in the same group.
+    ///
+    /// Each group is first packed into its own block, which is then packed into the
+    /// container as a single unrotatable item, so a group's items always end up in
+    /// one contiguous region (useful for keeping the frames of an animation together).
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation, Rect, pack};
+    /// let mut items: Vec<Item<usize>> = (0..8).map(|i| Item::new(i, 8, 8, Rotation::None)).collect();
+    /// items.push(Item::new(8, 16, 4, Rotation::Allowed));
+    /// for item in &mut items[..4] {
+    ///     item.group = Some(1);
+    /// }
+    /// let packed = pack(Rect::of_size(64, 64), items).unwrap();
+    /// let (frames, others): (Vec<_>, Vec<_>) = packed.iter().partition(|p| p.data < 4);
+    ///
+    /// // every frame is next to another frame...
+    /// let touches = |a: &Rect, b: &Rect| {
+    ///     let grown = Rect::new(a.x.saturating_sub(1), a.y.saturating_sub(1), a.w + 2, a.h + 2);
+    ///     grown.overlaps(b)
+    /// };
+    /// for a in &frames {
+    ///     assert!(frames.iter().any(|b| a.data != b.data && touches(&a.rect, &b.rect)));
+    /// }
+    ///
+    /// // ...and together they tightly fill one block nothing else is packed into
+    /// let x = frames.iter().map(|p| p.rect.x).min().unwrap();
+    /// let y = frames.iter().map(|p| p.rect.y).min().unwrap();
+    /// let block = Rect::new(x, y, 16, 16);
+    /// assert!(frames.iter().all(|p| block.contains(&p.rect)));
+    /// assert!(others.iter().all(|p| !block.overlaps(&p.rect)));
+    /// ```
+    pub group: Option<u32>,
+
+    /// If set, the item may only be packed somewhere inside this region of the container.
+    ///
+    /// This is ignored for items in a [`group`](Item::group), since their group's block
+    /// is packed as a whole.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation, Rect, pack};
+    /// let mut items: Vec<Item<usize>> = (0..16).map(|i| Item::new(i, 4, 4, Rotation::None)).collect();
+    ///
+    /// // keep the first two items within the top-left quadrant
+    /// let top_left = Rect::of_size(8, 8);
+    /// items[0].allowed = Some(top_left);
+    /// items[1].allowed = Some(top_left);
+    ///
+    /// // the rest fill whatever space is left, so the container ends up full
+    /// let packed = pack(Rect::of_size(16, 16), items).unwrap();
+    /// assert_eq!(packed.len(), 16);
+    /// for item in packed.iter().filter(|p| p.data < 2) {
+    ///     assert!(top_left.contains(&item.rect));
+    /// }
+    /// ```
+    pub allowed: Option<Rect>,
 }
 
 impl<T> Item<T> {
     /// Creates a new packing item.
     #[inline]
     pub fn new(data: T, w: usize, h: usize, rot: Rotation) -> Self {
-        Self { data, w, h, rot }
+        Self {
+            data,
+            w,
+            h,
+            rot,
+            sort_override: None,
+            weight: 1.0,
+            scale: 1,
+            optional: false,
+            user_rotation: 0,
+            lock_aspect: false,
+            group: None,
+            allowed: None,
+        }
     }
 
+    /// Creates a new `size` x `size` packing item.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation};
+    /// let item = Item::square('A', 8, Rotation::None);
+    /// assert_eq!((item.w, item.h), (8, 8));
+    /// ```
     #[inline]
-    pub(crate) fn sort_priority(&self) -> usize {
-        let area = self.w * self.h;
-        let longest_side = self.w.max(self.h);
-        area + longest_side
+    pub fn square(data: T, size: usize, rot: Rotation) -> Self {
+        Self::new(data, size, size, rot)
+    }
+
+    /// Creates a new packing item that may not be rotated.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation};
+    /// let item = Item::no_rotation('A', 8, 4);
+    /// assert_eq!(item.rot, Rotation::None);
+    /// ```
+    #[inline]
+    pub fn no_rotation(data: T, w: usize, h: usize) -> Self {
+        Self::new(data, w, h, Rotation::None)
+    }
+
+    /// Creates a new packing item that may be rotated 90° to fit better.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation};
+    /// let item = Item::rotatable('A', 8, 4);
+    /// assert_eq!(item.rot, Rotation::Allowed);
+    /// ```
+    #[inline]
+    pub fn rotatable(data: T, w: usize, h: usize) -> Self {
+        Self::new(data, w, h, Rotation::Allowed)
+    }
+
+    /// The size the item takes up when packed, which is its size times its
+    /// [`scale`](Item::scale).
+    #[inline]
+    pub fn packed_size(&self) -> (usize, usize) {
+        let scale = self.scale as usize;
+        (self.w.saturating_mul(scale), self.h.saturating_mul(scale))
+    }
+
+    /// Returns a copy of the item with its `data` borrowed.
+    #[inline]
+    pub fn as_ref(&self) -> Item<&T> {
+        Item {
+            data: &self.data,
+            w: self.w,
+            h: self.h,
+            rot: self.rot,
+            sort_override: self.sort_override,
+            weight: self.weight,
+            scale: self.scale,
+            optional: self.optional,
+            user_rotation: self.user_rotation,
+            lock_aspect: self.lock_aspect,
+            group: self.group,
+            allowed: self.allowed,
+        }
+    }
+
+    /// The priority the item is packed with, highest first.
+    ///
+    /// Unless overridden by [`sort_override`](Item::sort_override), this is the item's
+    /// area plus its longest side (multiplied by its [`weight`](Item::weight)), so the
+    /// largest items are packed first.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation, Rect, pack};
+    /// let mut small = Item::new('S', 2, 2, Rotation::None);
+    /// let large = Item::new('L', 8, 8, Rotation::None);
+    /// assert_eq!(small.sort_priority(), 6);
+    /// assert_eq!(large.sort_priority(), 72);
+    ///
+    /// // overriding the priority forces the small item to be packed first
+    /// small.sort_override = Some(100);
+    /// let packed = pack(Rect::of_size(16, 16), [large, small]).unwrap();
+    /// assert_eq!(packed[0].data, 'S');
+    /// assert_eq!(packed[0].rect, Rect::new(0, 0, 2, 2));
+    /// ```
+    ///
+    /// For items too large for this to fit in a `usize`, it saturates at `usize::MAX`
+    /// instead of overflowing, so packing untrusted sizes fails rather than panicking.
+    ///
+    /// ```
+    /// # use crunch::{Item, Packer, Rotation, Rect};
+    /// let huge = Item::new('H', usize::MAX / 2, usize::MAX / 2, Rotation::Allowed);
+    /// assert_eq!(huge.sort_priority(), usize::MAX);
+    ///
+    /// let mut packer = Packer::with_items([huge.clone(), huge.clone()]);
+    /// assert!(packer.pack(Rect::of_size(1024, 1024)).is_err());
+    /// assert_eq!(packer.largest_item().map(|(index, ..)| index), Some(0));
+    ///
+    /// // and the same goes for grouping them
+    /// let mut grouped = huge;
+    /// grouped.group = Some(0);
+    /// let mut packer = Packer::with_items([grouped.clone(), grouped]);
+    /// assert!(packer.pack(Rect::of_size(1024, 1024)).is_err());
+    /// ```
+    #[inline]
+    pub fn sort_priority(&self) -> usize {
+        self.sort_override.unwrap_or_else(|| {
+            let (w, h) = self.packed_size();
+            let priority = w.saturating_mul(h).saturating_add(w.max(h));
+            match self.weight {
+                1.0 => priority,
+                weight => (priority as f64 * weight as f64) as usize,
+            }
+        })
+    }
+}
+
+impl<T: Clone> Item<T> {
+    /// Splits the item into a grid of tiles, each no larger than `max_w` x `max_h`
+    /// when packed, such as for a sprite too large for the largest texture allowed.
+    ///
+    /// Each tile is returned with the part of the item it covers, with `(0, 0)` at the
+    /// item's top-left, a row of tiles at a time from the top. Tiles are as large as
+    /// they can be, so only the last column and row can be smaller. Every tile is a
+    /// copy of the item (with the same data and settings), other than its size, so
+    /// once packed, each is drawn with the part of the item it covers. If the item is
+    /// [scaled](Item::scale), the limits apply to its scaled size, and an item with no
+    /// area at all is returned as its only tile.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rect, Rotation};
+    /// let tiles = Item::new('A', 300, 300, Rotation::None).tile(128, 128);
+    /// assert_eq!(tiles.len(), 9);
+    ///
+    /// let sources: Vec<_> = tiles.iter().map(|(_, source)| *source).collect();
+    /// assert_eq!(&sources[..3], [
+    ///     Rect::new(0, 0, 128, 128),
+    ///     Rect::new(128, 0, 128, 128),
+    ///     Rect::new(256, 0, 44, 128),
+    /// ]);
+    /// assert_eq!(sources[8], Rect::new(256, 256, 44, 44));
+    ///
+    /// // every tile is the size of the part it covers, and they cover all of the item
+    /// for (tile, source) in &tiles {
+    ///     assert_eq!((tile.data, tile.w, tile.h), ('A', source.w, source.h));
+    /// }
+    /// assert_eq!(sources.iter().map(|source| source.area()).sum::<usize>(), 300 * 300);
+    ///
+    /// // an empty item isn't split up at all
+    /// let tiles = Item::new('B', 0, 300, Rotation::None).tile(128, 128);
+    /// assert_eq!(tiles.len(), 1);
+    /// assert_eq!(tiles[0].1, Rect::of_size(0, 300));
+    /// ```
+    pub fn tile(self, max_w: usize, max_h: usize) -> Vec<(Item<T>, Rect)> {
+        if self.w == 0 || self.h == 0 {
+            let source = Rect::of_size(self.w, self.h);
+            return vec![(self, source)];
+        }
+
+        let scale = (self.scale as usize).max(1);
+        let tile_w = (max_w / scale).max(1);
+        let tile_h = (max_h / scale).max(1);
+        let cols = self.w.div_ceil(tile_w).max(1);
+        let rows = self.h.div_ceil(tile_h).max(1);
+
+        let mut tiles = Vec::with_capacity(cols * rows);
+        for row in 0..rows {
+            for col in 0..cols {
+                let (x, y) = (col * tile_w, row * tile_h);
+                let source = Rect::new(x, y, tile_w.min(self.w - x), tile_h.min(self.h - y));
+                let mut tile = self.clone();
+                tile.w = source.w;
+                tile.h = source.h;
+                tiles.push((tile, source));
+            }
+        }
+        tiles
+    }
+}
+
+#[cfg(feature = "image")]
+impl Item<std::path::PathBuf> {
+    /// Creates a new packing item the size of the image at `path`, with the path as
+    /// its data so the image can be loaded later.
+    ///
+    /// Only the image's header is read to find its size, so this is much faster than
+    /// loading the whole image when you only need to pack it. This needs the `image`
+    /// feature, which only enables PNG support in the [`image`](https://docs.rs/image)
+    /// crate; enable more formats by depending on `image` with their features.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation};
+    /// let item = Item::from_image_dimensions("examples/pack_images/img/img0.png", Rotation::None)?;
+    /// assert_eq!((item.w, item.h), (10, 20));
+    /// assert!(item.data.ends_with("img0.png"));
+    ///
+    /// assert!(Item::from_image_dimensions("missing.png", Rotation::None).is_err());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn from_image_dimensions<P>(path: P, rot: Rotation) -> std::io::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        use std::io::{Error, ErrorKind};
+        let path = path.as_ref();
+        let (w, h) = image::image_dimensions(path).map_err(|err| match err {
+            image::ImageError::IoError(err) => err,
+            err => Error::new(ErrorKind::InvalidData, err),
+        })?;
+        Ok(Self::new(path.to_path_buf(), w as usize, h as usize, rot))
     }
 }
 
+/// Escapes the characters in `text` that aren't allowed in XML text.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 /// A container of packed items.
+#[derive(Debug)]
 pub struct PackedItems<T> {
     /// The width of the container.
     pub w: usize,
@@ -60,15 +491,636 @@ pub struct PackedItems<T> {
     pub items: Vec<PackedItem<T>>,
 }
 
+impl<T> PackedItems<T> {
+    /// The bounds of the container, positioned at the origin.
+    ///
+    /// This is the exact container the items were packed into, since packing into a
+    /// power of 2 always starts the container at the origin.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation, Rect, pack_into_po2};
+    /// let items = [
+    ///     Item::new('A', 10, 6, Rotation::Allowed),
+    ///     Item::new('B', 6, 6, Rotation::Allowed),
+    /// ];
+    /// let packed = pack_into_po2(64, items).unwrap();
+    ///
+    /// // Side by side, they fit in 16x8, and every item fits inside the container.
+    /// assert_eq!(packed.bounds(), Rect::of_size(16, 8));
+    /// for rect in packed.rects() {
+    ///     assert!(packed.bounds().contains(rect));
+    /// }
+    /// ```
+    #[inline]
+    pub fn bounds(&self) -> Rect {
+        Rect::of_size(self.w, self.h)
+    }
+
+    /// Iterates over the packed items, in the same order as [`items`](PackedItems::items).
+    #[inline]
+    pub fn iter_items(&self) -> impl Iterator<Item = &PackedItem<T>> + '_ {
+        self.items.iter()
+    }
+
+    /// Consumes the container, returning just the packed items, such as for exporting
+    /// each of them on their own.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation, pack_into_po2};
+    /// let items = [
+    ///     Item::new('A', 8, 8, Rotation::Allowed),
+    ///     Item::new('B', 8, 4, Rotation::Allowed),
+    ///     Item::new('C', 4, 4, Rotation::Allowed),
+    /// ];
+    /// let packed = pack_into_po2(64, items).unwrap();
+    /// let data: Vec<char> = packed.iter_items().map(|item| item.data).collect();
+    /// assert_eq!(data, ['A', 'B', 'C']);
+    ///
+    /// // taking the items out keeps them in the same order
+    /// let rects: Vec<_> = packed.rects().copied().collect();
+    /// let items = packed.into_items();
+    /// assert_eq!(items.len(), 3);
+    /// assert!(items.iter().zip(rects).all(|(item, rect)| item.rect == rect));
+    /// ```
+    #[inline]
+    pub fn into_items(self) -> Vec<PackedItem<T>> {
+        self.items
+    }
+
+    /// The rect each item was packed into, in the same order as [`items`](PackedItems::items).
+    #[inline]
+    pub fn rects(&self) -> impl Iterator<Item = &Rect> + '_ {
+        self.items.iter().map(|item| &item.rect)
+    }
+
+    /// Whether each item was [`rotated`](PackedItem::rotated), in the same order as
+    /// [`items`](PackedItems::items).
+    ///
+    /// ```
+    /// # use crunch::{Item, PackedItems, Rect, pack};
+    /// // the first item has to be rotated to fit
+    /// let items = [Item::rotatable('A', 4, 16), Item::no_rotation('B', 16, 4)];
+    /// let items = pack(Rect::of_size(16, 8), items).unwrap();
+    /// let packed = PackedItems { w: 16, h: 8, items };
+    ///
+    /// let rotations: Vec<bool> = packed.rotations().collect();
+    /// assert_eq!(rotations, [true, false]);
+    /// ```
+    #[inline]
+    pub fn rotations(&self) -> impl Iterator<Item = bool> + '_ {
+        self.items.iter().map(|item| item.rotated)
+    }
+
+    /// Finds the item packed into exactly `rect`, or `None` if no item was.
+    ///
+    /// This scans every item in order, so it takes O(n) time. If you need to look up
+    /// many rects in a large set, build a map from them once instead.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation, Rect, pack_into_po2};
+    /// let items = [Item::new('A', 8, 8, Rotation::None), Item::new('B', 8, 8, Rotation::None)];
+    /// let packed = pack_into_po2(64, items).unwrap();
+    ///
+    /// // the two squares sit side by side
+    /// let rect = Rect::new(8, 0, 8, 8);
+    /// assert_eq!(packed.find_by_rect(&rect).map(|item| item.data), Some('B'));
+    ///
+    /// // the rect has to match exactly, not just overlap
+    /// let moved = Rect::new(7, 0, 8, 8);
+    /// assert!(packed.find_by_rect(&moved).is_none());
+    /// ```
+    pub fn find_by_rect(&self, rect: &Rect) -> Option<&PackedItem<T>> {
+        self.items.iter().find(|item| item.rect == *rect)
+    }
+
+    /// The fraction of the container's area covered by packed items, from `0.0` to `1.0`.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation, pack_into_po2};
+    /// let items = (0..4).map(|i| Item::new(i, 8, 8, Rotation::None));
+    /// let packed = pack_into_po2(64, items).unwrap();
+    /// assert_eq!((packed.w, packed.h), (16, 16));
+    /// assert_eq!(packed.occupancy(), 1.0);
+    /// ```
+    pub fn occupancy(&self) -> f64 {
+        match self.bounds().area() {
+            0 => 0.0,
+            area => self.items.iter().map(|i| i.rect.area()).sum::<usize>() as f64 / area as f64,
+        }
+    }
+
+    /// The fraction of `region`'s area covered by packed items, from `0.0` to `1.0`
+    /// (or `0.0` if `region` is [empty](Rect::is_empty)).
+    ///
+    /// This is for finding under-used parts of the container, such as ones worth
+    /// reclaiming. Items only count for the part of them inside `region`, and any of
+    /// `region` outside the container counts as uncovered.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rect, Rotation, pack_into_po2};
+    /// let items = (0..3).map(|i| Item::new(i, 8, 8, Rotation::None));
+    /// let packed = pack_into_po2(64, items).unwrap();
+    /// assert_eq!((packed.w, packed.h), (16, 16));
+    ///
+    /// // the region covers parts of all 3 items, and the missing 4th corner
+    /// assert_eq!(packed.coverage_in(&Rect::new(4, 4, 8, 8)), 0.75);
+    ///
+    /// // half of this region lies outside of the container
+    /// assert_eq!(packed.coverage_in(&Rect::new(0, 8, 8, 16)), 0.5);
+    /// assert_eq!(packed.coverage_in(&Rect::new(2, 2, 0, 4)), 0.0);
+    /// ```
+    pub fn coverage_in(&self, region: &Rect) -> f64 {
+        match region.area() {
+            0 => 0.0,
+            area => {
+                let covered: usize = self.items.iter().map(|i| i.rect.overlap_area(region)).sum();
+                (covered as f64 / area as f64).min(1.0)
+            }
+        }
+    }
+
+    /// Calls `f` with the data, rect, and rotation of every packed item, for drawing
+    /// each of them onto the container.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation, pack_into_po2};
+    /// // pack some solid colors, and draw them onto a framebuffer
+    /// let items = (1..=4).map(|color| Item::new(color, 3 * color as usize, 2, Rotation::Allowed));
+    /// let packed = pack_into_po2(64, items).unwrap();
+    ///
+    /// let mut pixels = vec![0u8; packed.w * packed.h];
+    /// packed.blit(|&color, rect, _rotated| {
+    ///     for y in rect.y..rect.bottom() {
+    ///         for x in rect.x..rect.right() {
+    ///             pixels[y * packed.w + x] = color;
+    ///         }
+    ///     }
+    /// });
+    ///
+    /// for color in 1..=4 {
+    ///     let count = pixels.iter().filter(|&&p| p == color).count();
+    ///     assert_eq!(count, 3 * color as usize * 2);
+    /// }
+    /// ```
+    pub fn blit<F: FnMut(&T, &Rect, bool)>(&self, mut f: F) {
+        for item in &self.items {
+            f(&item.data, &item.rect, item.rotated);
+        }
+    }
+
+    /// Draws the container and its items as an SVG image, for debugging layouts.
+    ///
+    /// Each item is drawn as a colored rectangle with the text `label` returns for it
+    /// in the middle, and rotated items are hatched with diagonal lines. The SVG is
+    /// the same size as the container, in pixels.
+    ///
+    /// ```
+    /// # use crunch::{Item, pack_into_po2};
+    /// let items = (0..5).map(|i| Item::rotatable(i, 4 + i, 8));
+    /// let packed = pack_into_po2(64, items).unwrap();
+    ///
+    /// let svg = packed.to_svg(|i| format!("item {}", i));
+    /// assert!(svg.starts_with("<svg"));
+    /// assert!(svg.contains(&format!("width=\"{}\" height=\"{}\"", packed.w, packed.h)));
+    /// assert_eq!(svg.matches("<rect").count(), 5);
+    /// assert!(svg.contains(">item 3</text>"));
+    /// ```
+    pub fn to_svg<F: Fn(&T) -> String>(&self, label: F) -> String {
+        let (w, h) = (self.w, self.h);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+            w, h, w, h
+        );
+        svg.push_str(concat!(
+            "<defs><pattern id=\"hatch\" width=\"4\" height=\"4\" patternUnits=\"userSpaceOnUse\">",
+            "<path d=\"M0,4 L4,0\" stroke=\"black\" stroke-opacity=\"0.5\"/>",
+            "</pattern></defs>\n",
+        ));
+        for (i, item) in self.items.iter().enumerate() {
+            let Rect { x, y, w, h } = item.rect;
+
+            // spread the colors of neighbouring items out around the color wheel
+            let hue = (i * 137) % 360;
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"hsl({}, 70%, 60%)\" stroke=\"black\" stroke-width=\"0.5\"/>\n",
+                x, y, w, h, hue
+            ));
+            if item.rotated {
+                svg.push_str(&format!(
+                    "<path d=\"M{},{} h{} v{} h-{} Z\" fill=\"url(#hatch)\"/>\n",
+                    x, y, w, h, w
+                ));
+            }
+            let size = (w.min(h) / 2).clamp(1, 12);
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>\n",
+                x as f64 + w as f64 / 2.0,
+                y as f64 + h as f64 / 2.0,
+                size,
+                escape_xml(&label(&item.data))
+            ));
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Checks that no two packed items overlap, returning the indices of the first
+    /// overlapping pair found if any do.
+    ///
+    /// The packer never places items so that they overlap, so this is a way to check
+    /// that contract holds (for example, after modifying the rects yourself). Items
+    /// with a zero width or height don't cover any area, so they never overlap.
+    /// This compares every pair of items, so it is `O(n²)`.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation, Rect, PackedItem, pack_into_po2};
+    /// let items = [Item::new('A', 8, 8, Rotation::None), Item::new('B', 8, 8, Rotation::None)];
+    /// let mut packed = pack_into_po2(64, items).unwrap();
+    /// assert_eq!(packed.validate_no_overlap(), Ok(()));
+    ///
+    /// // a third item slipped in on top of the second overlaps it
+    /// packed.items.push(PackedItem {
+    ///     data: 'C',
+    ///     rect: packed.items[1].rect,
+    ///     rotated: false,
+    ///     scale: 1,
+    ///     optional: false,
+    ///     user_rotation: 0,
+    /// });
+    /// assert_eq!(packed.validate_no_overlap(), Err((1, 2)));
+    /// ```
+    pub fn validate_no_overlap(&self) -> Result<(), (usize, usize)> {
+        for (i, a) in self.items.iter().enumerate() {
+            for (j, b) in self.items.iter().enumerate().skip(i + 1) {
+                if a.rect.overlaps(&b.rect) {
+                    return Err((i, j));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// A hash of the container's size and where every item was packed (and if it was
+    /// rotated), for cheaply checking if a layout changed.
+    ///
+    /// The hash doesn't depend on the items' data or the order they're listed in, and
+    /// is the same across runs and platforms, so it can be saved by build tools.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation, pack_into_po2};
+    /// let items = || (0..10).map(|i| Item::new(i, 2 + i, 12 - i, Rotation::Allowed));
+    /// let a = pack_into_po2(64, items()).unwrap();
+    /// let b = pack_into_po2(64, items()).unwrap();
+    /// assert_eq!(a.layout_hash(), b.layout_hash());
+    ///
+    /// // changing an item's size changes the layout
+    /// let c = pack_into_po2(64, items().map(|mut i| { i.w += 1; i })).unwrap();
+    /// assert_ne!(a.layout_hash(), c.layout_hash());
+    /// ```
+    pub fn layout_hash(&self) -> u64 {
+        let mut layout: Vec<(Rect, bool)> =
+            self.items.iter().map(|i| (i.rect, i.rotated)).collect();
+        layout.sort_unstable_by_key(|(r, rotated)| (r.y, r.x, r.w, r.h, *rotated));
+
+        // FNV-1a, since std's hashers aren't guaranteed to stay the same
+        const PRIME: u64 = 0x100000001b3;
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let mut write = |value: usize| {
+            for byte in (value as u64).to_le_bytes() {
+                hash = (hash ^ byte as u64).wrapping_mul(PRIME);
+            }
+        };
+        write(self.w);
+        write(self.h);
+        for (rect, rotated) in layout {
+            write(rect.x);
+            write(rect.y);
+            write(rect.w);
+            write(rect.h);
+            write(rotated as usize);
+        }
+        hash
+    }
+
+    /// Places `other` to the right of this container, returning a container wide
+    /// enough for both (and as tall as the taller one) with all their items.
+    ///
+    /// This is useful for combining groups of items packed separately without
+    /// packing them again.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation, pack_into_po2};
+    /// let a = pack_into_po2(64, (0..4).map(|i| Item::new(i, 8, 8, Rotation::None))).unwrap();
+    /// let b = pack_into_po2(64, (4..6).map(|i| Item::new(i, 4, 4, Rotation::None))).unwrap();
+    /// let (a_w, b_rect) = (a.w, b.items[0].rect);
+    ///
+    /// let merged = a.merge_horizontal(b);
+    /// assert_eq!((merged.w, merged.h), (16 + 8, 16));
+    /// assert_eq!(merged.items.len(), 6);
+    /// assert_eq!(merged.items[4].rect.x, b_rect.x + a_w);
+    /// assert_eq!(merged.items[4].rect.y, b_rect.y);
+    /// assert_eq!(merged.validate_no_overlap(), Ok(()));
+    /// ```
+    pub fn merge_horizontal(mut self, other: Self) -> Self {
+        let offset = self.w;
+        self.items.extend(other.items.into_iter().map(|mut item| {
+            item.rect.x += offset;
+            item
+        }));
+        self.w += other.w;
+        self.h = self.h.max(other.h);
+        self
+    }
+
+    /// Places `other` below this container, returning a container tall enough for
+    /// both (and as wide as the wider one) with all their items.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rotation, pack_into_po2};
+    /// let a = pack_into_po2(64, (0..4).map(|i| Item::new(i, 8, 8, Rotation::None))).unwrap();
+    /// let b = pack_into_po2(64, (4..6).map(|i| Item::new(i, 4, 4, Rotation::None))).unwrap();
+    /// let (a_h, b_rect) = (a.h, b.items[0].rect);
+    ///
+    /// let merged = a.merge_vertical(b);
+    /// assert_eq!((merged.w, merged.h), (16, 16 + 4));
+    /// assert_eq!(merged.items[4].rect.x, b_rect.x);
+    /// assert_eq!(merged.items[4].rect.y, b_rect.y + a_h);
+    /// assert_eq!(merged.validate_no_overlap(), Ok(()));
+    /// ```
+    pub fn merge_vertical(mut self, other: Self) -> Self {
+        let offset = self.h;
+        self.items.extend(other.items.into_iter().map(|mut item| {
+            item.rect.y += offset;
+            item
+        }));
+        self.w = self.w.max(other.w);
+        self.h += other.h;
+        self
+    }
+
+    /// Consumes the packed items, returning a map from each item's data to the rect it
+    /// was packed into and whether it was rotated.
+    ///
+    /// If several items have the same data, the one packed last wins.
+    ///
+    /// ```
+    /// # use crunch::{Item, Rect, pack_into_po2};
+    /// let items = [
+    ///     Item::rotatable("player", 16, 32),
+    ///     Item::rotatable("enemy", 16, 16),
+    ///     Item::rotatable("coin", 8, 8),
+    /// ];
+    /// let map = pack_into_po2(64, items).unwrap().into_map();
+    /// assert_eq!(map.len(), 3);
+    ///
+    /// let (rect, rotated) = map["coin"];
+    /// assert_eq!((rect.w, rect.h), (8, 8));
+    /// assert!(!rotated);
+    /// ```
+    pub fn into_map(self) -> HashMap<T, (Rect, bool)>
+    where
+        T: Eq + Hash,
+    {
+        self.items
+            .into_iter()
+            .map(|item| (item.data, (item.rect, item.rotated)))
+            .collect()
+    }
+}
+
+#[cfg(feature = "binary")]
+impl<T> PackedItems<T> {
+    /// Writes the container and where every item was packed to `writer`, in a compact
+    /// binary format, calling `write_data` to write each item's data after its rect.
+    ///
+    /// Every number is little-endian. The container's width and height, and the number
+    /// of items, come first as `u64`s. Then for each item, its rect's `x`, `y`, `w`,
+    /// and `h` as `u64`s, a `u8` that is `1` if it was rotated and `0` otherwise, and
+    /// then whatever `write_data` wrote for it. Only these are written, so the items'
+    /// other fields aren't kept. This needs the `binary` feature.
+    ///
+    /// ```
+    /// # use crunch::{Item, PackedItems, Rotation, pack_into_po2};
+    /// use std::io::{Read, Write};
+    ///
+    /// let items = (0..6u16).map(|i| Item::new(i, 3 + i as usize, 2, Rotation::Allowed));
+    /// let packed = pack_into_po2(64, items).unwrap();
+    ///
+    /// let mut bytes = Vec::new();
+    /// packed
+    ///     .write_binary(&mut bytes, |&data, w| w.write_all(&data.to_le_bytes()))
+    ///     .unwrap();
+    /// assert_eq!(bytes.len(), 24 + 6 * (33 + 2));
+    ///
+    /// let read = PackedItems::read_binary(&mut bytes.as_slice(), |r| {
+    ///     let mut data = [0; 2];
+    ///     r.read_exact(&mut data)?;
+    ///     Ok(u16::from_le_bytes(data))
+    /// })
+    /// .unwrap();
+    /// assert_eq!((read.w, read.h), (packed.w, packed.h));
+    /// for (a, b) in read.items.iter().zip(&packed.items) {
+    ///     assert_eq!((a.data, a.rect, a.rotated), (b.data, b.rect, b.rotated));
+    /// }
+    /// ```
+    pub fn write_binary<W, F>(&self, writer: &mut W, mut write_data: F) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+        F: FnMut(&T, &mut W) -> std::io::Result<()>,
+    {
+        let write_u64 = |writer: &mut W, n: usize| writer.write_all(&(n as u64).to_le_bytes());
+        write_u64(writer, self.w)?;
+        write_u64(writer, self.h)?;
+        write_u64(writer, self.items.len())?;
+        for item in &self.items {
+            let Rect { x, y, w, h } = item.rect;
+            for n in [x, y, w, h] {
+                write_u64(writer, n)?;
+            }
+            writer.write_all(&[item.rotated as u8])?;
+            write_data(&item.data, writer)?;
+        }
+        Ok(())
+    }
+
+    /// Reads packed items written by [`write_binary`](PackedItems::write_binary) from
+    /// `reader`, calling `read_data` to read each item's data after its rect.
+    ///
+    /// Since only the rects and rotations are written, every item is read back with a
+    /// [`scale`](PackedItem::scale) of `1`, not [`optional`](PackedItem::optional), and
+    /// a [`user_rotation`](PackedItem::user_rotation) of `0`. If the data is cut short
+    /// or a number doesn't fit in a `usize`, this fails. This needs the `binary` feature.
+    pub fn read_binary<R, F>(reader: &mut R, mut read_data: F) -> std::io::Result<Self>
+    where
+        R: std::io::Read,
+        F: FnMut(&mut R) -> std::io::Result<T>,
+    {
+        use std::convert::TryFrom;
+        use std::io::{Error, ErrorKind};
+        let read_u64 = |reader: &mut R| {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            usize::try_from(u64::from_le_bytes(bytes))
+                .map_err(|err| Error::new(ErrorKind::InvalidData, err))
+        };
+        let (w, h, count) = (read_u64(reader)?, read_u64(reader)?, read_u64(reader)?);
+
+        // don't trust the count enough to reserve room for all of it up front
+        let mut items = Vec::with_capacity(count.min(1024));
+        for _ in 0..count {
+            let rect = Rect::new(
+                read_u64(reader)?,
+                read_u64(reader)?,
+                read_u64(reader)?,
+                read_u64(reader)?,
+            );
+            let mut rotated = [0];
+            reader.read_exact(&mut rotated)?;
+            let rotated = match rotated[0] {
+                0 => false,
+                1 => true,
+                n => {
+                    let msg = format!("invalid rotation flag {}", n);
+                    return Err(Error::new(ErrorKind::InvalidData, msg));
+                }
+            };
+            items.push(PackedItem {
+                data: read_data(reader)?,
+                rect,
+                rotated,
+                scale: 1,
+                optional: false,
+                user_rotation: 0,
+            });
+        }
+        Ok(Self { w, h, items })
+    }
+}
+
 /// An item that has been packed into a container.
+#[derive(Debug)]
 pub struct PackedItem<T> {
     /// The data associated with the item.
     pub data: T,
 
     /// The position where the item was packed.
     ///
-    /// If the item was packed with [`Rotation::Allowed`], you can compare
-    /// the rectangle's width with the input width you provided. If they
-    /// differ, it means the item was rotated to fit better.
+    /// If the item was rotated, the rectangle's width and height are swapped
+    /// from the input size you provided. If it was scaled, so is the rectangle.
+    pub rect: Rect,
+
+    /// Whether the item was rotated 90° to fit better.
+    ///
+    /// This can only be `true` for items packed with [`Rotation::Allowed`] or
+    /// [`Rotation::Auto`], and is `true` if `rect` is the item's size turned sideways.
+    pub rotated: bool,
+
+    /// The [`scale`](Item::scale) the item was packed at.
+    pub scale: u32,
+
+    /// Whether the item was [`optional`](Item::optional).
+    pub optional: bool,
+
+    /// The item's [`user_rotation`](Item::user_rotation), unchanged.
+    pub user_rotation: u16,
+}
+
+impl<T> PackedItem<T> {
+    /// The size of the item before it was scaled (but after it was rotated).
+    #[inline]
+    pub fn logical_size(&self) -> (usize, usize) {
+        let scale = (self.scale as usize).max(1);
+        (self.rect.w / scale, self.rect.h / scale)
+    }
+}
+
+/// The result of [`Packer::pack_indexed`](crate::Packer::pack_indexed): the packed
+/// items, and which of the packer's items each of them is.
+#[derive(Debug)]
+pub struct IndexedPack<T> {
+    /// The packed items, in the order they were packed.
+    pub packed: PackedItems<T>,
+
+    /// The index (in the order they were added to the packer) of the item at each
+    /// position of [`packed`](IndexedPack::packed)'s items.
+    pub indices: Vec<usize>,
+}
+
+/// One item being placed by [`Packer::pack_traced`](crate::Packer::pack_traced).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackStep {
+    /// The index (in the order they were added to the packer) of the item placed.
+    pub index: usize,
+
+    /// The rect the item was packed into.
     pub rect: Rect,
+
+    /// The rects of the free space nodes that were split off when the item was placed.
+    pub created: Vec<Rect>,
+}
+
+/// The result of [`Packer::pack_outcome`](crate::Packer::pack_outcome): the items that
+/// were packed, and the ones that weren't.
+#[derive(Debug)]
+pub struct PackOutcome<'a, T> {
+    pub(crate) packed: Vec<PackedItem<T>>,
+    pub(crate) unpacked: Vec<Item<&'a T>>,
+    pub(crate) complete: bool,
+}
+
+impl<'a, T> PackOutcome<'a, T> {
+    /// Returns `true` if every item was packed.
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// The items that were packed.
+    #[inline]
+    pub fn packed(&self) -> &[PackedItem<T>] {
+        &self.packed
+    }
+
+    /// The items that weren't packed.
+    #[inline]
+    pub fn unpacked(&self) -> &[Item<&'a T>] {
+        &self.unpacked
+    }
+
+    /// Consumes the outcome, returning the packed items in the same form as
+    /// [`Packer::pack`](crate::Packer::pack) does. They're only `Ok` if the outcome
+    /// [`is_complete`](PackOutcome::is_complete), so unlike `pack`, this is an `Err` if
+    /// any optional or oversized items were left out.
+    pub fn into_result(self) -> Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>> {
+        match self.complete {
+            true => Ok(self.packed),
+            false => Err(self.packed),
+        }
+    }
+}
+
+/// Converts the result of [`Packer::pack`](crate::Packer::pack). Since that doesn't
+/// say which items weren't packed, the outcome's [`unpacked`](PackOutcome::unpacked)
+/// is always empty.
+///
+/// ```
+/// # use crunch::{Item, PackOutcome, Rect, pack};
+/// let items = (0..3).map(|i| Item::no_rotation(i, 8, 8));
+/// let outcome = PackOutcome::from(pack(Rect::of_size(16, 8), items));
+/// assert!(!outcome.is_complete());
+/// assert_eq!(outcome.packed().len(), 2);
+/// assert!(outcome.into_result().is_err());
+/// ```
+impl<'a, T> From<Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>>> for PackOutcome<'a, T> {
+    fn from(result: Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>>) -> Self {
+        let (packed, complete) = match result {
+            Ok(packed) => (packed, true),
+            Err(packed) => (packed, false),
+        };
+        Self {
+            packed,
+            unpacked: Vec::new(),
+            complete,
+        }
+    }
 }
@@ -123,16 +123,79 @@ the tree that we do this will prevent us from ever having to overlap-check huge
 amounts of leaf nodes. My earlier versions of this just scanned the whole flat
 list of leaf nodes and... it was much slower.
 
+## Logging
+To see where and why each item was packed, enable the `log` feature:
+
+```toml
+[dependencies]
+crunch = { version = "0.5", features = ["log"] }
+```
+
+Every placement (and every node split it causes) is then logged at the `trace` level
+with the [`log`](https://docs.rs/log) crate, so a logger like `env_logger` can print
+it with `RUST_LOG=crunch=trace`. Without the feature, none of this is compiled in.
+
+## Images
+To pack image files by size without loading them, enable the `image` feature, which
+adds `Item::from_image_dimensions`. It also adds `pack_images_into_atlas`, which packs
+already loaded images and copies them all onto a single atlas image:
+
+```toml
+[dependencies]
+crunch = { version = "0.5", features = ["image"] }
+```
+
+## Binary format
+To save packed atlases in a compact binary format (rather than something like JSON),
+enable the `binary` feature, which adds `PackedItems::write_binary` and
+`PackedItems::read_binary`:
+
+```toml
+[dependencies]
+crunch = { version = "0.5", features = ["binary"] }
+```
+
 ## Contributions
 I'm happy to take pull requests if you manage to find ways to make it faster
 or more memory-friendly. If you have a non-obvious speed improvement change,
 I'd appreciate if you supplied a benchmark with it so I can see the effect.
 */
 
+//`map_or(true, ..)` stands in for `Option::is_none_or`, which needs Rust 1.82
+#![allow(clippy::unnecessary_map_or)]
+
+//logs at the trace level if the `log` feature is enabled, otherwise its arguments are
+//type-checked but never evaluated, so it compiles to nothing
+#[cfg(feature = "log")]
+macro_rules! trace {
+    ($($arg:tt)*) => { log::trace!($($arg)*) };
+}
+#[cfg(not(feature = "log"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+
+mod error;
+mod index;
 mod item;
 mod packer;
 mod rect;
-
-pub use item::{Item, PackedItem, PackedItems, Rotation};
-pub use packer::{pack, pack_into_po2, Packer};
+mod rect_packer;
+mod shelf;
+mod tree;
+
+pub use error::{PackError, ValidationError};
+pub use item::{IndexedPack, Item, PackOutcome, PackStep, PackedItem, PackedItems, Rotation};
+#[cfg(feature = "image")]
+pub use packer::pack_images_into_atlas;
+pub use packer::{
+    next_power_of_two_at_least, pack, pack_into_po2, Corner, Heuristic, PackChunks, Packer,
+    Scratch, SortOrder, Strategy, PO2_SHAPES,
+};
 pub use rect::Rect;
+pub use rect_packer::RectPacker;
+pub use tree::Score;
@@ -1,7 +1,19 @@
-use crate::item::{PackedItem, PackedItems};
-use crate::{Item, Rect, Rotation};
+use crate::item::{IndexedPack, PackOutcome, PackStep, PackedItem, PackedItems};
+use crate::shelf::Shelves;
+use crate::tree::{Tiebreak, Tree};
+use crate::{Item, PackError, Rect, Rotation, Score, ValidationError};
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashMap;
+use std::hash::Hash;
 use std::iter::*;
 
+#[cfg(test)]
+thread_local! {
+    //how many times `pack_prepared` was called on this thread, so tests can check how
+    //many containers a search tried
+    static PACK_PREPARED_CALLS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
 /// Attempts to tightly pack the supplied `items` into `into_rect`.
 ///
 /// Returns a collection of `Vec<(Rect, T)>` on success, or all items
@@ -57,6 +69,18 @@ where
 /// it possibly can, while not exceeding the provided `max_size`.
 ///
 /// On success, returns the size of the container (a power of 2) and the packed items.
+/// The container itself, as a `Rect`, is [`PackedItems::bounds`].
+/// If `max_size` isn't a power of 2, it is rounded down to the nearest one.
+///
+/// If there are no items to pack, this succeeds with an empty `0 x 0` container.
+///
+/// ```
+/// # use crunch::{pack_into_po2, Item};
+/// let packed = pack_into_po2(1024, Vec::<Item<char>>::new()).unwrap();
+/// assert_eq!((packed.w, packed.h), (0, 0));
+/// assert!(packed.items.is_empty());
+/// ```
+#[allow(clippy::result_unit_err)]
 pub fn pack_into_po2<T, I>(max_size: usize, items: I) -> Result<PackedItems<T>, ()>
 where
     T: Clone,
@@ -66,11 +90,497 @@ where
     packer.pack_into_po2(max_size)
 }
 
+/// The smallest power of 2 that is at least `n`, such as for the size of a container
+/// along one axis.
+///
+/// Both `0` and `1` give `1`, the smallest power of 2. If `n` is larger than the
+/// largest power of 2 a `usize` can hold, that power of 2 is returned instead.
+///
+/// ```
+/// # use crunch::next_power_of_two_at_least;
+/// assert_eq!(next_power_of_two_at_least(0), 1);
+/// assert_eq!(next_power_of_two_at_least(1), 1);
+/// assert_eq!(next_power_of_two_at_least(2), 2);
+/// assert_eq!(next_power_of_two_at_least(3), 4);
+/// assert_eq!(next_power_of_two_at_least(1000), 1024);
+/// assert_eq!(next_power_of_two_at_least(1024), 1024);
+///
+/// // it saturates at the largest power of 2 that fits
+/// let largest = 1 << (usize::BITS - 1);
+/// assert_eq!(next_power_of_two_at_least(largest), largest);
+/// assert_eq!(next_power_of_two_at_least(largest + 1), largest);
+/// assert_eq!(next_power_of_two_at_least(usize::MAX), largest);
+/// ```
+pub const fn next_power_of_two_at_least(n: usize) -> usize {
+    match n.checked_next_power_of_two() {
+        Some(size) => size,
+        None => 1 << (usize::BITS - 1),
+    }
+}
+
+/// Packs `images` into the smallest power of 2 atlas it can, while not exceeding
+/// the provided `max_size`, and copies them all onto it.
+///
+/// On success, returns the atlas along with where each image was placed, in the
+/// same order as `images`, and whether it was rotated 90° clockwise to fit. This
+/// needs the `image` feature.
+///
+/// ```
+/// # use crunch::{pack_images_into_atlas, Rect};
+/// use image::{Rgba, RgbaImage};
+///
+/// // a few solid squares, plus a tall strip with a red top and blue bottom
+/// let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+/// let mut images: Vec<_> = colors
+///     .iter()
+///     .map(|&color| RgbaImage::from_pixel(5, 5, Rgba(color)))
+///     .collect();
+/// images.push(RgbaImage::from_fn(2, 8, |_, y| Rgba(colors[if y < 4 { 0 } else { 2 }])));
+///
+/// let (atlas, placed) = pack_images_into_atlas(images.clone(), 64).unwrap();
+/// assert_eq!(placed.len(), images.len());
+///
+/// // the strip is laid on its side under the squares
+/// assert!(placed[3].1);
+///
+/// for (img, &(rect, rotated)) in images.iter().zip(&placed) {
+///     for (x, y, pixel) in img.enumerate_pixels() {
+///         // rotating clockwise moves the image's left column to the top row
+///         let (x, y) = match rotated {
+///             true => (rect.x + (img.height() - 1 - y) as usize, rect.y + x as usize),
+///             false => (rect.x + x as usize, rect.y + y as usize),
+///         };
+///         assert!(rect.contains(&Rect::new(x, y, 1, 1)));
+///         assert_eq!(atlas.get_pixel(x as u32, y as u32), pixel);
+///     }
+/// }
+///
+/// // too big for the largest allowed atlas
+/// assert!(pack_images_into_atlas(vec![RgbaImage::new(65, 1)], 64).is_err());
+/// ```
+#[cfg(feature = "image")]
+#[allow(clippy::result_unit_err, clippy::type_complexity)]
+pub fn pack_images_into_atlas(
+    images: Vec<image::RgbaImage>,
+    max_size: usize,
+) -> Result<(image::RgbaImage, Vec<(Rect, bool)>), ()> {
+    use image::GenericImage;
+
+    // pack the images' indices, so the images themselves don't need cloning
+    let items = images.iter().enumerate().map(|(i, img)| {
+        let (w, h) = (img.width() as usize, img.height() as usize);
+        Item::new(i, w, h, Rotation::Allowed)
+    });
+    let packed = Packer::with_items(items).pack_into_po2(max_size)?;
+
+    let mut atlas = image::RgbaImage::new(packed.w as u32, packed.h as u32);
+    let mut placed = vec![(Rect::default(), false); images.len()];
+    for PackedItem {
+        data,
+        rect,
+        rotated,
+        ..
+    } in packed.items
+    {
+        let (x, y) = (rect.x as u32, rect.y as u32);
+        let copied = match rotated {
+            true => atlas.copy_from(&image::imageops::rotate90(&images[data]), x, y),
+            false => atlas.copy_from(&images[data], x, y),
+        };
+        copied.map_err(|_| ())?;
+        placed[data] = (rect, rotated);
+    }
+
+    Ok((atlas, placed))
+}
+
+/// How a [`Packer`] decides where to place each item.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Strategy {
+    /// Place each item in the free space that fits it best, tracked with a tree
+    /// of free space (see the crate documentation for how it works).
+    ///
+    /// This is the default, and packs mixed item sizes very tightly.
+    #[default]
+    Tree,
+
+    /// Place items left to right along horizontal shelves, each item going on the
+    /// open shelf it fits with the least height to spare. New shelves are opened
+    /// below the last one when an item doesn't fit on any of them.
+    ///
+    /// This is much faster than [`Strategy::Tree`], and packs items of similar
+    /// heights (like the glyphs of a font atlas) tightly.
+    Shelf,
+}
+
+/// How [`Strategy::Tree`] scores the free spaces an item could be packed into, to
+/// pick the best one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Heuristic {
+    /// Pick the space with the least area left over, then the one with the least left
+    /// over on its shorter side.
+    ///
+    /// This is the default, and usually packs the tightest.
+    #[default]
+    BestAreaFit,
+
+    /// Pick the space with the least left over on its shorter side, then the least left
+    /// over on its longer side.
+    BestShortSideFit,
+
+    /// Pick the space with the least left over on its longer side, then the least left
+    /// over on its shorter side.
+    BestLongSideFit,
+
+    /// Pick the space where the item's bottom edge would be highest up, then the one
+    /// furthest left, which fills the container from the top down.
+    TopLeft,
+
+    /// Packs exactly like [`BestAreaFit`](Heuristic::BestAreaFit) did in crunch 0.5,
+    /// and always will, even in later versions that change the default.
+    ///
+    /// The way items are sorted, scored, and placed for this heuristic is frozen, so
+    /// pinning it keeps layouts byte-for-byte identical across upgrades (as long as
+    /// the items and the packer's other options stay the same). Items are always
+    /// sorted by their size, like they were then, so their
+    /// [`sort_override`](Item::sort_override) and [`weight`](Item::weight) are ignored.
+    ///
+    /// ```
+    /// # use crunch::{Heuristic, Item, Packer, Rect, Rotation};
+    /// let item = |i| Item::new(i, 1 + i * 7 % 10, 1 + i * 3 % 8, Rotation::Allowed);
+    /// let packer = Packer::with_items((0..12).map(item)).with_heuristic(Heuristic::LegacyV1);
+    /// let packed = packer.pack_cloned(Rect::of_size(24, 24)).unwrap();
+    ///
+    /// // this layout must never change
+    /// let layout: Vec<_> = packed.iter().map(|item| (item.data, item.rect.into())).collect();
+    /// let golden: [(usize, (usize, usize, usize, usize)); 12] = [
+    ///     (7, (0, 0, 10, 6)),
+    ///     (5, (10, 0, 8, 6)),
+    ///     (4, (18, 0, 5, 9)),
+    ///     (2, (0, 6, 5, 7)),
+    ///     (1, (5, 6, 8, 4)),
+    ///     (11, (13, 6, 2, 8)),
+    ///     (9, (5, 10, 4, 4)),
+    ///     (8, (23, 0, 1, 7)),
+    ///     (10, (23, 7, 1, 7)),
+    ///     (6, (15, 6, 3, 3)),
+    ///     (3, (0, 13, 2, 2)),
+    ///     (0, (2, 13, 1, 1)),
+    /// ];
+    /// assert_eq!(layout, golden);
+    ///
+    /// // and overriding the order items are sorted in doesn't change it either
+    /// let items = (0..12).map(item).map(|mut item| {
+    ///     item.sort_override = Some(item.data);
+    ///     item
+    /// });
+    /// let packer = Packer::with_items(items).with_heuristic(Heuristic::LegacyV1);
+    /// let packed = packer.pack_cloned(Rect::of_size(24, 24)).unwrap();
+    /// let layout: Vec<_> = packed.iter().map(|item| (item.data, item.rect.into())).collect();
+    /// assert_eq!(layout, golden);
+    /// ```
+    LegacyV1,
+}
+
+/// The order a [`Packer`] packs its items in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// Pack the items with the highest [`sort_priority`](Item::sort_priority) first,
+    /// which is the largest ones.
+    ///
+    /// This is the default.
+    #[default]
+    Priority,
+
+    /// Pack the items grouped by their shape, and then by priority within each group.
+    ///
+    /// The items are grouped by their aspect ratio, to the nearest power of 2 (so
+    /// items about square, about twice as wide as they are tall, and so on), from the
+    /// widest to the tallest. Similar shapes leave similar leftovers when they're packed
+    /// side by side, so packing them together splits the free space up into fewer thin
+    /// slivers that nothing else fits into.
+    ///
+    /// This helps [`Strategy::Shelf`] the most, since a shelf of wide items has nowhere
+    /// to put the tall ones. [`Strategy::Tree`] already finds good spots for mixed
+    /// shapes, so it usually packs them a little tighter in the default order.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect, SortOrder, Strategy};
+    /// // a mix of wide and tall items
+    /// let items = (0..40).map(|i| match i % 2 {
+    ///     0 => Item::no_rotation(i, 12 + i % 5, 2 + i % 3),
+    ///     _ => Item::no_rotation(i, 2 + i % 3, 12 + i % 5),
+    /// });
+    /// let packer = Packer::with_items(items).with_strategy(Strategy::Shelf);
+    /// let rect = Rect::of_size(52, 52);
+    /// assert!(packer.pack_cloned(rect).is_err());
+    ///
+    /// // shelves of only wide or only tall items waste much less space
+    /// let packer = packer.with_sort_order(SortOrder::ShapeGrouped);
+    /// let packed = packer.pack_cloned(rect).unwrap();
+    /// let area: usize = packed.iter().map(|item| item.rect.area()).sum();
+    /// assert!(area as f64 / rect.area() as f64 > 0.6);
+    /// ```
+    ShapeGrouped,
+}
+
+/// The corner of the container that packed positions are measured from, see
+/// [`Packer::with_origin_corner`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Corner {
+    /// Positions are measured right and down from the top-left corner.
+    ///
+    /// This is the default.
+    #[default]
+    TopLeft,
+
+    /// Positions are measured left and down from the top-right corner.
+    TopRight,
+
+    /// Positions are measured right and up from the bottom-left corner, like in
+    /// OpenGL's texture coordinates.
+    BottomLeft,
+
+    /// Positions are measured left and up from the bottom-right corner.
+    BottomRight,
+}
+
+/// The intermediary allocations a [`Packer`] uses while packing.
+///
+/// This can be moved between packers with [`Packer::with_scratch`] and
+/// [`Packer::into_scratch`], so the allocations can be pooled.
+pub struct Scratch {
+    tree: Tree,
+    shelves: Shelves,
+    indices: Vec<usize>,
+    duplicates: Vec<(usize, usize)>,
+    packed_into: Rect,
+    placed: Vec<(Rect, usize)>,
+    unpacked: Vec<usize>,
+    rotated: usize,
+    placed_area: usize,
+}
+
+impl Scratch {
+    /// Create new, empty scratch allocations.
+    pub const fn new() -> Self {
+        Self {
+            tree: Tree::new(),
+            shelves: Shelves::new(),
+            indices: Vec::new(),
+            duplicates: Vec::new(),
+            packed_into: Rect::new(0, 0, 0, 0),
+            placed: Vec::new(),
+            unpacked: Vec::new(),
+            rotated: 0,
+            placed_area: 0,
+        }
+    }
+
+    //the fraction of `container_area` covered by the packed items
+    fn occupancy(&self, container_area: usize) -> f64 {
+        match container_area {
+            0 => 0.0,
+            area => self.placed_area as f64 / area as f64,
+        }
+    }
+
+    //forget the last layout, keeping the memory it used
+    fn clear(&mut self) {
+        self.tree.clear();
+        self.shelves.clear(Rect::new(0, 0, 0, 0));
+        self.indices.clear();
+        self.duplicates.clear();
+        self.packed_into = Rect::new(0, 0, 0, 0);
+        self.placed.clear();
+        self.unpacked.clear();
+        self.rotated = 0;
+        self.placed_area = 0;
+    }
+}
+
+impl Default for Scratch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The container shapes [`Packer::pack_into_po2`] tries at every power of 2 size, as
+/// `(w, h)` multipliers: square, twice as wide, and twice as tall.
+pub const PO2_SHAPES: [(usize, usize); 3] = [(1, 1), (2, 1), (1, 2)];
+
 /// A packer for items of type `Item<T>`.
 pub struct Packer<T> {
     items_to_pack: Vec<Item<T>>,
-    nodes: Vec<Node>,
+    scratch: Scratch,
+    use_index: bool,
+    strategy: Strategy,
+    heuristic: Heuristic,
+    skip_oversized: bool,
+    max_fragmentation: Option<f64>,
+    rotation_policy: Option<RotationPolicy<T>>,
+    dedup: Option<Dedup<T>>,
+    node_tiebreak: Option<Box<Tiebreak>>,
+    rotation_override: Option<Rotation>,
+    inter_group_spacing: usize,
+    node_reserve_factor: usize,
+    size_quantum: usize,
+    sort: SortOrder,
+    origin: Corner,
+    mask: Option<Mask>,
+    prepared: Option<Prepared>,
+}
+
+/// A grid of `cell_size` cells, `cols` wide and `rows` tall, of which only the
+/// `allowed` ones may be packed into, see [`Packer::with_mask`].
+struct Mask {
+    cols: usize,
+    rows: usize,
+    cell_size: usize,
+    allowed: Vec<bool>,
+}
+
+/// The order to pack the items in, cached by [`Packer::prepare`]. It is forgotten
+/// whenever the items, or the settings that decide how they're sorted or grouped,
+/// change.
+struct Prepared {
     indices: Vec<usize>,
+    duplicates: Vec<(usize, usize)>,
+    groups: Vec<Group>,
+}
+
+/// A group of items packed together into a `w` x `h` block, and the rect each
+/// member (by index) was packed into within it.
+struct Group {
+    w: usize,
+    h: usize,
+    members: Vec<(usize, Rect)>,
+    optional: bool,
+}
+
+impl Group {
+    /// The block is packed with the same priority an item of its size would be.
+    fn priority(&self) -> usize {
+        self.w
+            .saturating_mul(self.h)
+            .saturating_add(self.w.max(self.h))
+    }
+}
+
+//the `sort_priority` items had in crunch 0.5, frozen for `Heuristic::LegacyV1` (which
+//had no overrides or weights to change it, so they're ignored here)
+fn legacy_v1_priority<T>(item: &Item<T>) -> usize {
+    let (w, h) = item.packed_size();
+    w.saturating_mul(h).saturating_add(w.max(h))
+}
+
+/// Why `Packer::pack_with` stopped before packing every item.
+enum Stopped {
+    DoesNotFit,
+    TooFragmented { nodes: usize, limit: usize },
+}
+
+/// Decides if an item may be rotated into a candidate rect, see [`Packer::with_rotation_policy`].
+type RotationPolicy<T> = Box<dyn Fn(&Item<&T>, &Rect) -> bool + Send + Sync>;
+
+/// Finds the item each item shares its slot with, see [`Packer::with_dedup_by`].
+type Dedup<T> = Box<dyn Fn(&[Item<T>]) -> Vec<usize> + Send + Sync>;
+
+/// The duplicates sharing the slot of `item_i`, out of `duplicates`, a sorted list of
+/// `(representative, duplicate)` pairs.
+#[inline]
+fn duplicates_of(duplicates: &[(usize, usize)], item_i: usize) -> impl Iterator<Item = usize> + '_ {
+    let start = duplicates.partition_point(|&(rep, _)| rep < item_i);
+    duplicates[start..]
+        .iter()
+        .take_while(move |&&(rep, _)| rep == item_i)
+        .map(|&(_, dup)| dup)
+}
+
+/// How many times wider than it is tall a `w` x `h` rect is, as the nearest power of
+/// 2 (negative if it's taller than it is wide).
+#[inline]
+fn aspect_bucket(w: usize, h: usize) -> i32 {
+    (w as f64 / h as f64).log2().round() as i32
+}
+
+/// Shuffles `indices` into a random order, picked with the random numbers from
+/// `next_rand`.
+fn shuffle<R: FnMut() -> u64>(indices: &mut [usize], next_rand: &mut R) {
+    for i in (1..indices.len()).rev() {
+        let j = (next_rand() % (i as u64 + 1)) as usize;
+        indices.swap(i, j);
+    }
+}
+
+/// The smallest integer whose square is at least `n`.
+fn ceil_sqrt(n: usize) -> usize {
+    let mut root = (n as f64).sqrt() as usize;
+    while root.saturating_mul(root) < n {
+        root += 1;
+    }
+    while root > 0 && (root - 1) * (root - 1) >= n {
+        root -= 1;
+    }
+    root
+}
+
+/// `size` rounded up to the nearest multiple of `quantum`.
+#[inline]
+fn quantized(size: usize, quantum: usize) -> usize {
+    match size % quantum {
+        0 => size,
+        rem => size.saturating_add(quantum - rem),
+    }
+}
+
+/// `rect` grown by `pad` on its right and bottom.
+#[inline]
+fn padded(rect: &Rect, pad: usize) -> Rect {
+    Rect::new(
+        rect.x,
+        rect.y,
+        rect.w.saturating_add(pad),
+        rect.h.saturating_add(pad),
+    )
+}
+
+/// `rect`, packed from the top-left of `into_rect`, flipped across `into_rect` to be
+/// measured from `corner` instead (so flipping it again undoes it).
+#[inline]
+fn from_corner(corner: Corner, into_rect: &Rect, rect: Rect) -> Rect {
+    let (flip_x, flip_y) = match corner {
+        Corner::TopLeft => (false, false),
+        Corner::TopRight => (true, false),
+        Corner::BottomLeft => (false, true),
+        Corner::BottomRight => (true, true),
+    };
+    let x = match flip_x {
+        true => into_rect.x + (into_rect.right() - rect.right()),
+        false => rect.x,
+    };
+    let y = match flip_y {
+        true => into_rect.y + (into_rect.bottom() - rect.bottom()),
+        false => rect.y,
+    };
+    Rect::new(x, y, rect.w, rect.h)
+}
+
+/// Panics (in debug builds only) if `rect` was packed outside of `into_rect`.
+#[inline]
+fn debug_assert_inside(into_rect: &Rect, rect: &Rect) {
+    debug_assert!(
+        rect.is_empty() || into_rect.contains(rect),
+        "packed {:?} outside of {:?}",
+        rect,
+        into_rect
+    );
+}
+
+/// Returns true if `policy` allows `item` to be rotated into `rect`.
+#[inline]
+fn allows_rotation<T>(policy: &Option<RotationPolicy<T>>, item: &Item<&T>, rect: &Rect) -> bool {
+    policy.as_ref().map_or(true, |policy| policy(item, rect))
 }
 
 impl<T> Packer<T> {
@@ -78,8 +588,23 @@ impl<T> Packer<T> {
     pub const fn new() -> Self {
         Self {
             items_to_pack: Vec::new(),
-            nodes: Vec::new(),
-            indices: Vec::new(),
+            scratch: Scratch::new(),
+            use_index: false,
+            strategy: Strategy::Tree,
+            heuristic: Heuristic::BestAreaFit,
+            skip_oversized: false,
+            max_fragmentation: None,
+            rotation_policy: None,
+            dedup: None,
+            node_tiebreak: None,
+            rotation_override: None,
+            inter_group_spacing: 0,
+            node_reserve_factor: 2,
+            size_quantum: 1,
+            sort: SortOrder::Priority,
+            origin: Corner::TopLeft,
+            mask: None,
+            prepared: None,
         }
     }
 
@@ -87,8 +612,7 @@ impl<T> Packer<T> {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             items_to_pack: Vec::with_capacity(capacity),
-            nodes: Vec::new(),
-            indices: Vec::new(),
+            ..Self::new()
         }
     }
 
@@ -96,260 +620,3322 @@ impl<T> Packer<T> {
     pub fn with_items<I: IntoIterator<Item = Item<T>>>(items: I) -> Self {
         Self {
             items_to_pack: items.into_iter().collect(),
-            nodes: Vec::new(),
-            indices: Vec::new(),
+            ..Self::new()
         }
     }
-}
 
-impl<T> Default for Packer<T> {
-    /// Default packer, equivalent to `Packer::new()`.
-    fn default() -> Self {
-        Self::new()
+    /// Enable or disable indexing the free space of the packing tree by size.
+    ///
+    /// With the index enabled, finding the best place for each item can skip straight
+    /// past free space that is too small for it, instead of searching the whole tree.
+    /// This costs some extra bookkeeping for every item packed, but pays off when
+    /// packing thousands of items. The packed results are identical either way.
+    /// It is disabled by default.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let items: Vec<_> = (0..500)
+    ///     .map(|i| Item::new(i, 1 + i % 13, 1 + i % 7, Rotation::Allowed))
+    ///     .collect();
+    /// let rect = Rect::of_size(128, 128);
+    ///
+    /// let plain = Packer::with_items(items.clone()).pack(rect).unwrap_or_else(|p| p);
+    /// let indexed = Packer::with_items(items).with_index(true).pack(rect).unwrap_or_else(|p| p);
+    /// assert_eq!(plain.len(), indexed.len());
+    /// for (a, b) in plain.iter().zip(&indexed) {
+    ///     assert_eq!((a.data, a.rect), (b.data, b.rect));
+    /// }
+    /// ```
+    pub fn with_index(mut self, enabled: bool) -> Self {
+        self.use_index = enabled;
+        self
     }
-}
 
-impl<T: Clone> Packer<T> {
-    pub fn clear(&mut self) -> &mut Self {
-        self.items_to_pack.clear();
+    /// Set a policy deciding whether rotatable items may actually be rotated.
+    ///
+    /// Whenever an item with [`Rotation::Allowed`] or [`Rotation::Auto`] would fit better
+    /// rotated, `policy` is called with the item and the free space it would be rotated
+    /// into. If it returns `false`, the item is packed unrotated instead. Without a policy (the
+    /// default), rotatable items are always rotated when it fits them better.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let items = [Item::new('A', 2, 10, Rotation::Allowed)];
+    /// let rect = Rect::of_size(10, 2);
+    ///
+    /// // by default, the item is rotated so it fits
+    /// let packed = Packer::with_items(items.clone()).pack(rect).unwrap();
+    /// assert_eq!(packed[0].rect, Rect::new(0, 0, 10, 2));
+    ///
+    /// // but this policy doesn't allow it
+    /// let mut packer = Packer::with_items(items).with_rotation_policy(|_, rect| rect.w < 10);
+    /// assert!(packer.pack(rect).is_err());
+    /// ```
+    pub fn with_rotation_policy<F>(mut self, policy: F) -> Self
+    where
+        F: Fn(&Item<&T>, &Rect) -> bool + Send + Sync + 'static,
+    {
+        self.rotation_policy = Some(Box::new(policy));
+        self.prepared = None;
         self
     }
 
-    #[inline]
-    pub fn push(&mut self, item: Item<T>) -> &mut Self {
-        self.items_to_pack.push(item);
+    /// Pack items that have equal data only once, sharing a single slot, using `reducer`
+    /// to pick which of them decides how that slot is packed.
+    ///
+    /// For every pair of items with equal data, `reducer` is called with the item
+    /// picked so far and the next one, and returns whichever should be used, so the
+    /// slot is packed with that item's size, rotation, and other settings. Every item
+    /// with the same data is then returned packed into that slot (so they should all
+    /// have the same size, and will overlap each other exactly). Items in a
+    /// [`group`](Item::group) are never shared.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// // the same sprite, added twice with different rotation settings
+    /// let items = [
+    ///     Item::new("sprite", 4, 16, Rotation::None),
+    ///     Item::new("sprite", 4, 16, Rotation::Allowed),
+    ///     Item::new("other", 8, 4, Rotation::None),
+    /// ];
+    ///
+    /// // pick whichever allows rotation, which it needs to fit
+    /// let mut packer = Packer::with_items(items).with_dedup_by(|a, b| match a.rot {
+    ///     Rotation::None => b,
+    ///     _ => a,
+    /// });
+    /// let packed = packer.pack(Rect::of_size(16, 8)).unwrap();
+    /// assert_eq!(packed.len(), 3);
+    ///
+    /// let sprites: Vec<_> = packed.iter().filter(|item| item.data == "sprite").collect();
+    /// assert_eq!(sprites.len(), 2);
+    /// assert_eq!(sprites[0].rect, sprites[1].rect);
+    /// assert!(sprites.iter().all(|item| item.rotated));
+    /// ```
+    pub fn with_dedup_by<F>(mut self, reducer: F) -> Self
+    where
+        T: Eq + Hash,
+        F: for<'a, 'b> Fn(&'a Item<&'b T>, &'a Item<&'b T>) -> &'a Item<&'b T>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.dedup = Some(Box::new(move |items: &[Item<T>]| {
+            // the representative picked so far for each ungrouped item's data
+            let mut picked: HashMap<&T, usize> = HashMap::with_capacity(items.len());
+            for (i, item) in items.iter().enumerate() {
+                if item.group.is_some() {
+                    continue;
+                }
+                let best = picked.entry(&item.data).or_insert(i);
+                let (a, b) = (items[*best].as_ref(), item.as_ref());
+                if *best != i && std::ptr::eq(reducer(&a, &b), &b) {
+                    *best = i;
+                }
+            }
+            let rep = |(i, item): (usize, &Item<T>)| match item.group {
+                Some(_) => i,
+                None => picked[&item.data],
+            };
+            items.iter().enumerate().map(rep).collect()
+        }));
+        self.prepared = None;
         self
     }
 
-    #[inline]
-    pub fn extend<I: IntoIterator<Item = Item<T>>>(&mut self, items: I) -> &mut Self {
-        self.items_to_pack.extend(items);
+    /// Set how to choose between two free spaces an item fits into equally well.
+    ///
+    /// When the scores of two free spaces tie, `tiebreak` is called with both of
+    /// them, and the item is packed into the first if it returns
+    /// [`Ordering::Less`](std::cmp::Ordering::Less). Without a tiebreak (the default),
+    /// the first free space found wins. Only [`Strategy::Tree`] is affected.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect};
+    /// // after 'A', the spaces to its right and below it fit 'B' equally well
+    /// let items = [Item::no_rotation('A', 8, 8), Item::no_rotation('B', 8, 8)];
+    /// let packed = Packer::with_items(items.clone()).pack(Rect::of_size(16, 16)).unwrap();
+    /// assert_eq!(packed[1].rect, Rect::new(8, 0, 8, 8));
+    ///
+    /// // prefer the lowest space instead
+    /// let mut packer = Packer::with_items(items).with_node_tiebreak(|a, b| b.y.cmp(&a.y));
+    /// let packed = packer.pack(Rect::of_size(16, 16)).unwrap();
+    /// assert_eq!(packed[1].rect, Rect::new(0, 8, 8, 8));
+    /// ```
+    pub fn with_node_tiebreak<F>(mut self, tiebreak: F) -> Self
+    where
+        F: Fn(&Rect, &Rect) -> Ordering + Send + Sync + 'static,
+    {
+        self.node_tiebreak = Some(Box::new(tiebreak));
+        self.prepared = None;
         self
     }
 
-    //find the node that best fits a new rectangle of size (w, h)
-    #[inline]
-    fn find_best_node(&self, w: usize, h: usize, node_index: usize) -> (usize, Score) {
-        let node = &self.nodes[node_index];
-
-        // check if this node's branch could potentially hold the new rect
-        if w <= node.rect.w && h <= node.rect.h {
-            // check if the node is a branch or a leaf node
-            if node.is_split {
-                // for split nodes, recursively search each branch and find the best node
-                node.split.iter().filter(|&&i| i > 0).fold(
-                    (usize::MAX, Score::worst()),
-                    |(best_i, best_s), &child| {
-                        let (i, s) = self.find_best_node(w, h, child);
-                        if s.better_than(&best_s) {
-                            (i, s)
-                        } else {
-                            (best_i, best_s)
-                        }
-                    },
-                )
-            } else {
-                (node_index, Score::new(&node.rect, w, h))
-            }
-        } else {
-            (usize::MAX, Score::worst())
-        }
+    /// Use the allocations in `scratch` for the packer's intermediary data, so they can
+    /// be shared between many short-lived packers. Take them back afterwards with
+    /// [`into_scratch`](Packer::into_scratch).
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect, Scratch};
+    /// // pack a sheet of four icons for each icon size, all with the same allocations
+    /// let mut scratch = Scratch::new();
+    /// for size in [4, 8, 16] {
+    ///     let icons = (0..4).map(|i| Item::new(i, size, size, Rotation::None));
+    ///     let mut packer = Packer::with_items(icons).with_scratch(scratch);
+    ///     let packed = packer.pack(Rect::of_size(size * 2, size * 2)).unwrap();
+    ///
+    ///     // reusing the scratch allocations doesn't change the results
+    ///     assert_eq!(packed[3].rect, Rect::new(size, size, size, size));
+    ///     scratch = packer.into_scratch();
+    /// }
+    /// ```
+    pub fn with_scratch(mut self, scratch: Scratch) -> Self {
+        self.scratch = scratch;
+        self
     }
 
-    //returns true if any leaf node contains the supplied rect
-    #[inline]
-    fn leaf_contains_rect(&self, rect: &Rect, node_index: usize) -> bool {
-        let node = &self.nodes[node_index];
-        match node.rect.contains(rect) {
-            false => false,
-            true => {
-                !node.is_split
-                    || node
-                        .split
-                        .iter()
-                        .any(|&i| i > 0 && self.leaf_contains_rect(rect, i))
-            }
-        }
+    /// Consume the packer, returning its intermediary allocations so they can be
+    /// reused by another packer with [`with_scratch`](Packer::with_scratch).
+    pub fn into_scratch(self) -> Scratch {
+        self.scratch
     }
 
-    //split all nodes that overlap with this rectangle
-    #[inline]
-    fn split_tree(&mut self, rect: &Rect, node_index: usize) {
-        //if the rectangle overlaps with this branch of the tree
-        if self.nodes[node_index].rect.overlaps(rect) {
-            //if the node is already split, recursively split into its child nodes
-            if self.nodes[node_index].is_split {
-                let split = self.nodes[node_index].split;
-                for i in split.iter().cloned().filter(|&i| i > 0) {
-                    self.split_tree(rect, i);
-                }
-            } else {
-                //split the rect into 0-4 sub-rects and make a new node out of each
-                self.nodes[node_index].is_split = true;
-                let rects = self.nodes[node_index].rect.split(rect);
-                for i in 0..rects.len() {
-                    if let Some(r) = &rects[i] {
-                        //only add the child rect if no other leaf node contains it
-                        if !self.leaf_contains_rect(r, 0) {
-                            self.nodes[node_index].split[i] = self.nodes.len();
-                            self.nodes.push(Node {
-                                rect: *r,
-                                is_split: false,
-                                split: [0; 4],
-                            });
-                        }
-                    }
-                }
-            }
-        }
+    /// Override the [`rot`](Item::rot) setting of every item with `rotation`, or
+    /// use each item's own setting if `None` (the default).
+    ///
+    /// Overriding with `Some(Rotation::None)` means the packer never searches for
+    /// rotated fits at all (or calls the [rotation policy](Packer::with_rotation_policy)),
+    /// so it is the fastest way to pack large batches of items that shouldn't rotate.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let items = [Item::new('A', 4, 10, Rotation::None), Item::new('B', 4, 10, Rotation::None)];
+    /// let container = Rect::of_size(10, 8);
+    ///
+    /// // the items only fit lying down
+    /// let packer = Packer::with_items(items.clone());
+    /// assert!(packer.pack_cloned(container).is_err());
+    ///
+    /// let packer = packer.with_rotation_override(Some(Rotation::Allowed));
+    /// let packed = packer.pack_cloned(container).unwrap();
+    /// assert!(packed.iter().all(|item| item.rotated));
+    ///
+    /// // and forbidding rotation makes items that could rotate not fit again
+    /// let items = items.map(|item| Item::new(item.data, item.w, item.h, Rotation::Allowed));
+    /// let packer = Packer::with_items(items).with_rotation_override(Some(Rotation::None));
+    /// assert!(packer.pack_cloned(container).is_err());
+    /// ```
+    pub fn with_rotation_override(mut self, rotation: Option<Rotation>) -> Self {
+        self.rotation_override = rotation;
+        self.prepared = None;
+        self
     }
 
-    /// Attempt to pack all the items into `into_rect`. The returned `Vec<(Rect, T)>`
-    /// will contain positions for all packed items on success, or just the items
-    /// the packer was able to successfully pack before failing.
+    /// If `skip` is `true`, items too large to fit into the container even when it's
+    /// empty are skipped when packing, rather than failing the whole pack. The skipped
+    /// items can be found with [`unpacked`](Packer::unpacked) afterwards.
     ///
-    /// This function uses some internal intermediary collections, which is why
-    /// it is mutable, so it cannot be called but it is valid to call it multiple times with different
-    /// `into_rect` values.
+    /// ```
+    /// # use crunch::{Packer, Item, Rect};
+    /// let items = [
+    ///     Item::no_rotation('A', 8, 8),
+    ///     Item::no_rotation('B', 64, 4),
+    ///     Item::no_rotation('C', 8, 8),
+    /// ];
+    /// let mut packer = Packer::with_items(items).with_skip_oversized(true);
+    /// let packed = packer.pack(Rect::of_size(16, 16)).unwrap();
+    /// assert_eq!(packed.len(), 2);
     ///
-    /// If you want to attempt to pack the same item list into several different
-    /// `into_rect`, it is valid to call this function multiple times on the same
-    /// `Packer`, and it will re-use its intermediary data structures.
-    pub fn pack(&mut self, into_rect: Rect) -> Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>> {
-        // start with one node that is the full size of the rect
-        // reserve a deccent amount of room in the initial nodes vec
-        self.nodes.clear();
-        self.nodes.reserve(self.items_to_pack.len() * 2);
-        self.nodes.push(Node {
-            rect: into_rect,
-            is_split: false,
-            split: [0; 4],
-        });
+    /// let unpacked: Vec<char> = packer.unpacked().map(|item| item.data).collect();
+    /// assert_eq!(unpacked, ['B']);
+    /// ```
+    pub fn with_skip_oversized(mut self, skip: bool) -> Self {
+        self.skip_oversized = skip;
+        self
+    }
 
-        // indices of items we need to pack, sorted by their area
-        // the largest items should be packed first for best fits
-        self.indices.clear();
-        self.indices.extend(0..self.items_to_pack.len());
-        {
-            let items = &self.items_to_pack;
-            self.indices.sort_by(|&a, &b| {
-                let sort_a = items[a].sort_priority();
-                let sort_b = items[b].sort_priority();
-                sort_b.cmp(&sort_a)
-            });
-        }
+    /// Leave at least `spacing` pixels between items of different
+    /// [`group`](Item::group)s, while items in the same group are still packed flush
+    /// against each other. Ungrouped items count as being in a group of their own.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect};
+    /// let items = (0..8).map(|i| {
+    ///     let mut item = Item::no_rotation(i, 8, 8);
+    ///     item.group = Some(i / 4);
+    ///     item
+    /// });
+    /// let mut packer = Packer::with_items(items).with_inter_group_spacing(2);
+    /// let packed = packer.pack(Rect::of_size(64, 64)).unwrap();
+    ///
+    /// let gap = |a: &Rect, b: &Rect| {
+    ///     let x = b.x.saturating_sub(a.right()).max(a.x.saturating_sub(b.right()));
+    ///     let y = b.y.saturating_sub(a.bottom()).max(a.y.saturating_sub(b.bottom()));
+    ///     x.max(y)
+    /// };
+    /// for a in &packed {
+    ///     for b in packed.iter().filter(|b| b.data != a.data) {
+    ///         if a.data / 4 == b.data / 4 {
+    ///             // the 4 items in each group form a flush 16x16 block
+    ///             assert!(gap(&a.rect, &b.rect) == 0);
+    ///         } else {
+    ///             assert!(gap(&a.rect, &b.rect) >= 2);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn with_inter_group_spacing(mut self, spacing: usize) -> Self {
+        self.inter_group_spacing = spacing;
+        self
+    }
 
-        // list of packed items we'll return (whether we succeed or fail)
-        let mut packed = Vec::with_capacity(self.items_to_pack.len());
+    /// Only pack into the cells of a grid that `allowed` marks `true`, for packing into
+    /// a container that isn't rectangular.
+    ///
+    /// The grid is `cols` cells wide and `rows` cells tall, each `cell_size` x
+    /// `cell_size`, starting at the top-left of the container. `allowed` lists whether
+    /// each cell may be packed into, a row at a time from the top (any cells missing
+    /// from it aren't allowed). The free space is split around the cells that aren't
+    /// allowed before anything is packed, so items are only packed where every cell
+    /// they cover is allowed. The space outside of the grid isn't masked.
+    ///
+    /// Only [`Strategy::Tree`] packs around the mask, so it is ignored when packing
+    /// onto shelves.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// // an L-shaped container, missing its top-right quarter
+    /// let allowed = vec![true, false, true, true];
+    /// let items = (0..12).map(|i| Item::new(i, 8, 8, Rotation::None));
+    /// let mut packer = Packer::with_items(items).with_mask(2, 2, 16, allowed);
+    ///
+    /// let packed = packer.pack(Rect::of_size(32, 32)).unwrap();
+    /// let missing = Rect::new(16, 0, 16, 16);
+    /// assert!(packed.iter().all(|item| !item.rect.overlaps(&missing)));
+    ///
+    /// // the L only has room for 12 of these items, not all 16 of the square's
+    /// packer.push(Item::new(12, 8, 8, Rotation::None));
+    /// assert!(packer.pack(Rect::of_size(32, 32)).is_err());
+    /// ```
+    pub fn with_mask(
+        mut self,
+        cols: usize,
+        rows: usize,
+        cell_size: usize,
+        allowed: Vec<bool>,
+    ) -> Self {
+        self.mask = Some(Mask {
+            cols,
+            rows,
+            cell_size,
+            allowed,
+        });
+        self
+    }
 
-        // pack all items, longest sides -> shorted sides
-        // for &item_index in (&self.indices).into_iter().rev() {
-        for ind in 0..self.indices.len() {
-            let item = self.items_to_pack[self.indices[ind]].clone();
-
-            // find the best position to pack the item
-            // if the item is rotated 90º, pack_w and pack_h will be swapped
-            let mut pack_w = item.w;
-            let mut pack_h = item.h;
-            let (mut node_i, score) = self.find_best_node(item.w, item.h, 0);
-            if item.rot == Rotation::Allowed && item.w != item.h {
-                let (i, s) = self.find_best_node(item.h, item.w, 0);
-                if s.better_than(&score) {
-                    node_i = i;
-                    pack_w = item.h;
-                    pack_h = item.w;
-                }
-            }
+    /// Measure the packed items' positions from the container's `corner`, instead of
+    /// its top-left (the default).
+    ///
+    /// The items are still packed from the top-left down, and then their rects are
+    /// flipped across the container, so that each position is the item's corner
+    /// closest to `corner`, measured towards the container's center from it. This is
+    /// for coordinate systems that start from the bottom (like OpenGL's textures)
+    /// or the right. Everything else, like each item's [`allowed`](Item::allowed)
+    /// region and the [mask](Packer::with_mask), is still measured from the top-left.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect, Corner};
+    /// let items = [Item::no_rotation('A', 6, 4), Item::no_rotation('B', 3, 2)];
+    /// let container = Rect::new(10, 20, 10, 10);
+    /// let rects = |corner| {
+    ///     let packer = Packer::with_items(items.clone()).with_origin_corner(corner);
+    ///     let packed = packer.pack_cloned(container).unwrap();
+    ///     packed.iter().map(|item| item.rect).collect::<Vec<_>>()
+    /// };
+    ///
+    /// // the larger item is packed in the corner, with the smaller one beside it
+    /// assert_eq!(rects(Corner::TopLeft), [Rect::new(10, 20, 6, 4), Rect::new(16, 20, 3, 2)]);
+    /// assert_eq!(rects(Corner::TopRight), [Rect::new(14, 20, 6, 4), Rect::new(11, 20, 3, 2)]);
+    /// assert_eq!(rects(Corner::BottomLeft), [Rect::new(10, 26, 6, 4), Rect::new(16, 28, 3, 2)]);
+    /// assert_eq!(rects(Corner::BottomRight), [Rect::new(14, 26, 6, 4), Rect::new(11, 28, 3, 2)]);
+    /// ```
+    pub fn with_origin_corner(mut self, corner: Corner) -> Self {
+        self.origin = corner;
+        self
+    }
 
-            // if we failed to pack the item, return failure
-            // and everything we did manage to pack
-            if node_i == usize::MAX {
-                return Err(packed);
-            }
+    /// Round the space reserved for each item up to the nearest multiple of `quantum`
+    /// in both directions (by default it is `1`, so sizes aren't rounded).
+    ///
+    /// Many items of slightly different sizes split the free space up into lots of
+    /// slightly different leftovers, which are often too small to use. Rounding their
+    /// sizes makes those leftovers line up, so they can be reused by other items. The
+    /// packed rects are still the items' own sizes, sat at the top-left of the rounded
+    /// space reserved for them, so no item is packed into the rest of that space.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect, Rotation, PackError};
+    /// let items: Vec<_> = (0..120)
+    ///     .map(|i| Item::new(i, 5 + i * 7 % 4, 5 + i * 3 % 4, Rotation::Allowed))
+    ///     .collect();
+    /// let rect = Rect::of_size(128, 128);
+    ///
+    /// // unrounded, the free space splits up more than the limit allows
+    /// let mut packer = Packer::with_items(items.clone()).with_max_fragmentation(1.5);
+    /// assert!(matches!(packer.try_pack(rect), Err(PackError::TooFragmented { .. })));
+    ///
+    /// // but rounded to multiples of 8, it splits up far less
+    /// let mut packer = packer.with_size_quantum(8);
+    /// let packed = packer.try_pack(rect).unwrap();
+    /// for packed in &packed {
+    ///     let item = &items[packed.data];
+    ///     assert_eq!((packed.rect.x % 8, packed.rect.y % 8), (0, 0));
+    ///     assert_eq!(packed.rect.area(), item.w * item.h);
+    /// }
+    /// ```
+    pub fn with_size_quantum(mut self, quantum: usize) -> Self {
+        self.size_quantum = quantum.max(1);
+        self.prepared = None;
+        self
+    }
 
-            // get the final rectangle where the item will be packed
-            let (node_x, node_y) = self.nodes[node_i].rect.top_left();
-            let rect = Rect::new(node_x, node_y, pack_w, pack_h);
+    /// Reserve room for `factor` times as many free space nodes as there are items
+    /// before packing (`2` by default), or `0` to not reserve any.
+    ///
+    /// Items that split the free space up a lot can need far more nodes than the
+    /// default, so raising this avoids growing the nodes while packing. It doesn't
+    /// change where anything gets packed.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let items = [('A', 12, 4), ('B', 4, 12), ('C', 12, 8)];
+    /// let items = items.map(|(data, w, h)| Item::new(data, w, h, Rotation::None));
+    ///
+    /// // however much room is reserved, everything goes in the same place
+    /// for factor in [0, 2, 8] {
+    ///     let packer = Packer::with_items(items.clone()).with_node_reserve_factor(factor);
+    ///     let packed = packer.pack_cloned(Rect::of_size(16, 12)).unwrap();
+    ///     let layout: Vec<_> = packed.iter().map(|item| (item.data, item.rect.into())).collect();
+    ///     assert_eq!(layout, [('C', (0, 0, 12, 8)), ('A', (0, 8, 12, 4)), ('B', (12, 0, 4, 12))]);
+    /// }
+    /// ```
+    pub fn with_node_reserve_factor(mut self, factor: usize) -> Self {
+        self.node_reserve_factor = factor;
+        self
+    }
 
-            // split the tree on the new item's rect to create new packing branches
-            self.split_tree(&rect, 0);
+    /// Give up packing with [`PackError::TooFragmented`] if the free space ever gets
+    /// split into more than `ratio` times as many pieces as there are items.
+    ///
+    /// Some inputs split the free space up far more than usual, which uses a lot of
+    /// memory and time, so this guards against them when packing untrusted items.
+    /// Only [`Strategy::Tree`] is affected, since the other strategies don't split
+    /// free space up this way.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect, PackError};
+    /// // thin slivers of alternating orientation split the free space up a lot
+    /// let items = (0..64).map(|i| match i % 2 {
+    ///     0 => Item::no_rotation(i, 1, 1 + i % 7),
+    ///     _ => Item::no_rotation(i, 1 + i % 5, 1),
+    /// });
+    /// let mut packer = Packer::with_items(items).with_max_fragmentation(1.0);
+    /// match packer.try_pack(Rect::of_size(256, 256)) {
+    ///     Err(PackError::TooFragmented { nodes, limit, .. }) => assert!(nodes > limit),
+    ///     _ => unreachable!(),
+    /// }
+    ///
+    /// // but given more room to fragment, they pack fine
+    /// let mut packer = packer.with_max_fragmentation(8.0);
+    /// assert!(packer.try_pack(Rect::of_size(256, 256)).is_ok());
+    /// ```
+    pub fn with_max_fragmentation(mut self, ratio: f64) -> Self {
+        self.max_fragmentation = Some(ratio);
+        self
+    }
 
-            // add the item to the successfully packed list
-            packed.push(PackedItem {
-                data: item.data,
-                rect,
-            })
+    /// The items that were left out by the last [`pack`](Packer::pack), either for
+    /// being too large for the container (see
+    /// [`with_skip_oversized`](Packer::with_skip_oversized)), or because they didn't
+    /// fit into either container of [`pack_overflow`](Packer::pack_overflow).
+    pub fn unpacked(&self) -> impl Iterator<Item = &Item<T>> + '_ {
+        self.scratch
+            .unpacked
+            .iter()
+            .map(move |&i| &self.items_to_pack[i])
+    }
+
+    //the rotation setting to pack `item` with
+    #[inline]
+    fn rotation_of<D>(&self, item: &Item<D>) -> Rotation {
+        match item.lock_aspect {
+            true => Rotation::None,
+            false => self.rotation_override.unwrap_or(item.rot),
         }
+    }
 
-        Ok(packed)
+    /// Set the [`Strategy`] used to decide where items get packed.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect, Strategy};
+    /// // glyphs of the same height all share rows
+    /// let glyphs = [('a', 5), ('b', 6), ('c', 4), ('d', 5), ('e', 6)]
+    ///     .map(|(glyph, w)| Item::new(glyph, w, 8, Rotation::None));
+    /// let packed = Packer::with_items(glyphs)
+    ///     .with_strategy(Strategy::Shelf)
+    ///     .pack(Rect::of_size(16, 16))
+    ///     .unwrap();
+    ///
+    /// // the widest glyphs start the first row, and the narrowest still fits at its end
+    /// let rows: Vec<_> = packed.iter().map(|item| (item.data, item.rect.y)).collect();
+    /// assert_eq!(rows, [('b', 0), ('e', 0), ('a', 8), ('d', 8), ('c', 0)]);
+    /// ```
+    pub fn with_strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self.prepared = None;
+        self
     }
 
-    /// Attempts to pack the supplied items into the smallest power of 2 container
-    /// it possibly can while not exceeding the provided `max_size`.
+    /// Set the [`Heuristic`] [`Strategy::Tree`] uses to pick where each item is packed.
     ///
-    /// On success, returns the size of the container (a power of 2) and the packed items.
-    pub fn pack_into_po2(&mut self, max_size: usize) -> Result<PackedItems<T>, ()> {
-        let min_area = self.items_to_pack.iter().map(|i| i.w * i.h).sum();
+    /// ```
+    /// # use crunch::{Packer, Item, Rect, Heuristic};
+    /// let items = (0..8).map(|i| Item::no_rotation(i, 4, 4));
+    /// let packed = Packer::with_items(items)
+    ///     .with_heuristic(Heuristic::TopLeft)
+    ///     .pack(Rect::of_size(16, 16))
+    ///     .unwrap();
+    ///
+    /// // the items fill the top half of the container
+    /// assert!(packed.iter().all(|item| item.rect.bottom() <= 8));
+    /// ```
+    pub fn with_heuristic(mut self, heuristic: Heuristic) -> Self {
+        self.heuristic = heuristic;
+        self.prepared = None;
+        self
+    }
 
-        let mut size = 2;
-        while size * size * 2 < min_area {
-            size *= 2;
-        }
+    /// Set the [`SortOrder`] the items are packed in.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect, SortOrder};
+    /// let items = [(16, 2), (2, 16), (12, 2), (2, 12)];
+    /// let items = items.iter().enumerate().map(|(i, &(w, h))| Item::no_rotation(i, w, h));
+    /// let packed = Packer::with_items(items)
+    ///     .with_sort_order(SortOrder::ShapeGrouped)
+    ///     .pack(Rect::of_size(20, 20))
+    ///     .unwrap();
+    ///
+    /// // the wide items are packed before the tall ones, even the larger tall one
+    /// let order: Vec<_> = packed.iter().map(|item| item.data).collect();
+    /// assert_eq!(order, [0, 2, 1, 3]);
+    /// ```
+    pub fn with_sort_order(mut self, order: SortOrder) -> Self {
+        self.sort = order;
+        self.prepared = None;
+        self
+    }
 
-        while size <= max_size {
-            for (w, h) in [(size, size), (size * 2, size), (size, size * 2)] {
-                if w <= max_size && h <= max_size && w * h >= min_area {
-                    if let Ok(items) = self.pack(Rect::of_size(w, h)) {
-                        return Ok(PackedItems { w, h, items });
-                    }
+    /// The number of items queued to be packed.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation};
+    /// let mut packer = Packer::new();
+    /// assert!(packer.is_empty());
+    ///
+    /// packer.push(Item::new('A', 10, 20, Rotation::None));
+    /// packer.push(Item::new('B', 30, 40, Rotation::None));
+    /// assert_eq!(packer.len(), 2);
+    /// assert_eq!(packer.total_area(), 10 * 20 + 30 * 40);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.items_to_pack.len()
+    }
+
+    /// Returns `true` if there are no items queued to be packed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.items_to_pack.is_empty()
+    }
+
+    /// The combined area of all the items queued to be packed, saturating at
+    /// `usize::MAX` if it is too large for a `usize`.
+    ///
+    /// No container with a smaller area than this could possibly fit all the items,
+    /// so [`pack_into_po2`](Packer::pack_into_po2) never tries one.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation};
+    /// let mut packer = Packer::with_items((0..10).map(|i| Item::new(i, 7, 5, Rotation::None)));
+    /// assert_eq!(packer.total_area(), 350);
+    ///
+    /// let packed = packer.pack_into_po2(1024).unwrap();
+    /// assert!(packed.w * packed.h >= packer.total_area());
+    ///
+    /// packer.push(Item::new(10, usize::MAX / 4, 8, Rotation::None));
+    /// assert_eq!(packer.total_area(), usize::MAX);
+    /// ```
+    #[inline]
+    pub fn total_area(&self) -> usize {
+        self.checked_total_area().unwrap_or(usize::MAX)
+    }
+
+    //the combined area of all the items, or `None` if it overflows
+    fn checked_total_area(&self) -> Option<usize> {
+        self.items_to_pack.iter().try_fold(0usize, |total, item| {
+            let (w, h) = (item.w, item.h);
+            let scale = item.scale as usize;
+            let area = w.checked_mul(h)?.checked_mul(scale)?.checked_mul(scale)?;
+            total.checked_add(area)
+        })
+    }
+
+    /// A lower bound on the size of a square container that could hold all the queued
+    /// items, returned as `(w, h)`.
+    ///
+    /// The container must be at least as large as the longest side of any item, and
+    /// have at least as much area as all the items combined. This bound isn't binding:
+    /// the items may not actually fit into it, but they will never fit into anything
+    /// smaller. It is useful for picking a sensible `max_size` for `pack_into_po2`.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation};
+    /// let mut packer = Packer::with_items((0..16).map(|i| Item::new(i, 4, 4, Rotation::None)));
+    /// assert_eq!(packer.min_bound(), (16, 16));
+    ///
+    /// // a single wide item dominates the bound
+    /// packer.push(Item::new(16, 100, 1, Rotation::Allowed));
+    /// assert_eq!(packer.min_bound(), (100, 100));
+    /// ```
+    pub fn min_bound(&self) -> (usize, usize) {
+        let longest_side = self
+            .items_to_pack
+            .iter()
+            .map(|i| i.packed_size())
+            .map(|(w, h)| w.max(h))
+            .max()
+            .unwrap_or(0);
+        let side = ceil_sqrt(self.total_area()).max(longest_side);
+        (side, side)
+    }
+
+    /// A cheap check for whether the queued items could possibly fit into `rect`,
+    /// without packing them.
+    ///
+    /// This returns `false` if the items' combined area is larger than `rect`'s, or any
+    /// item is too large to fit into `rect` even when rotated (if it may be). Items
+    /// that packing could leave out ([`optional`](Item::optional) ones, and oversized
+    /// ones when [skipping them](Packer::with_skip_oversized)) aren't counted.
+    ///
+    /// A `false` result means [`pack`](Packer::pack) is certain to fail, but `true`
+    /// doesn't guarantee it will succeed, since the items may still not be able to be
+    /// arranged to fit.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let items = (0..4).map(|i| Item::new(i, 8, 8, Rotation::None));
+    /// let mut packer = Packer::with_items(items);
+    /// assert!(packer.can_possibly_fit(Rect::of_size(16, 16)));
+    ///
+    /// // not enough area for all four items
+    /// assert!(!packer.can_possibly_fit(Rect::of_size(16, 15)));
+    ///
+    /// // enough area, but one item is too wide either way around
+    /// packer.push(Item::new(4, 40, 1, Rotation::Allowed));
+    /// assert!(!packer.can_possibly_fit(Rect::of_size(32, 32)));
+    ///
+    /// // an item that is only too wide until it's rotated can fit
+    /// let mut packer = Packer::with_items([Item::new('A', 4, 40, Rotation::Allowed)]);
+    /// assert!(packer.can_possibly_fit(Rect::of_size(40, 4)));
+    ///
+    /// // but `true` doesn't mean packing will succeed
+    /// let mut packer = Packer::with_items((0..2).map(|i| Item::new(i, 3, 3, Rotation::None)));
+    /// assert!(packer.can_possibly_fit(Rect::of_size(5, 5)));
+    /// assert!(packer.pack(Rect::of_size(5, 5)).is_err());
+    /// ```
+    pub fn can_possibly_fit(&self, rect: Rect) -> bool {
+        let mut area = 0usize;
+        for item in self.items_to_pack.iter().filter(|item| !item.optional) {
+            let (w, h) = item.packed_size();
+            let rotate = self.rotation_of(item) != Rotation::None;
+            if !Self::fits(&rect, w, h, rotate) {
+                match self.skip_oversized {
+                    true => continue,
+                    false => return false,
                 }
             }
-            size *= 2;
+            area = area.saturating_add(w.saturating_mul(h));
         }
+        area <= rect.area()
+    }
 
-        Err(())
+    /// Returns the index and packed `(w, h)` size of the queued item with the greatest
+    /// [`sort_priority`](Item::sort_priority), or `None` if there are no items.
+    ///
+    /// When packing fails, this is usually the item to blame, such as for reporting which
+    /// item to shrink or how much to raise a maximum size by. Similarly, the `index` of
+    /// [`ValidationError::TooLarge`] can be looked up in the queued items. If several
+    /// items tie, the first one is returned.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let mut packer = Packer::new();
+    /// assert_eq!(packer.largest_item(), None);
+    ///
+    /// packer.push(Item::new('A', 10, 10, Rotation::None));
+    /// let mut b = Item::new('B', 2000, 4, Rotation::None);
+    /// b.scale = 2;
+    /// packer.push(b);
+    /// packer.push(Item::new('C', 30, 30, Rotation::None));
+    /// assert_eq!(packer.largest_item(), Some((1, 4000, 8)));
+    ///
+    /// // the culprit is why this pack fails
+    /// assert!(packer.pack(Rect::of_size(2048, 2048)).is_err());
+    /// ```
+    pub fn largest_item(&self) -> Option<(usize, usize, usize)> {
+        self.items_to_pack
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, item)| std::cmp::Reverse(item.sort_priority()))
+            .map(|(index, item)| {
+                let (w, h) = item.packed_size();
+                (index, w, h)
+            })
+    }
+
+    /// Drop the parts of the last layout's free space tree that are completely full,
+    /// releasing the memory they used.
+    ///
+    /// Packing already does this whenever the tree has grown far larger than the
+    /// free space it tracks, so this is only needed to trim a packer that is being
+    /// kept around. It doesn't change where anything gets packed.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// // four squares fill the container, leaving only full branches
+    /// let mut packer = Packer::with_items((0..4).map(|i| Item::new(i, 8, 8, Rotation::None)));
+    /// let packed = packer.pack(Rect::of_size(16, 16)).unwrap();
+    /// assert_eq!(packer.tree_depth(), 2);
+    ///
+    /// packer.compact();
+    /// assert_eq!(packer.tree_depth(), 0);
+    /// assert_eq!(packer.free_area(), 0);
+    ///
+    /// // and swapping an item out still puts the new one where the old one was
+    /// let data = 4;
+    /// let added = packer.repack_delta(&[Item::new(&data, 8, 8, Rotation::None)], &[packed[2].rect]);
+    /// assert_eq!(added.unwrap()[0].rect, packed[2].rect);
+    /// ```
+    pub fn compact(&mut self) {
+        self.scratch.tree.compact();
+    }
+
+    /// How many levels deep the last layout's free space tree goes below its root,
+    /// or `0` if nothing has been packed with [`Strategy::Tree`].
+    ///
+    /// Every item packed splits the free space it lands on, so deep trees mean the
+    /// free space has been split up a lot, and searching it is slower. This can be
+    /// used to decide when to [`compact`](Packer::compact).
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect};
+    /// let mut packer = Packer::with_items([Item::no_rotation('A', 8, 8)]);
+    /// assert_eq!(packer.tree_depth(), 0);
+    ///
+    /// // the container splits into the free space right of and below the item
+    /// packer.pack(Rect::of_size(16, 16)).unwrap();
+    /// assert_eq!(packer.tree_depth(), 1);
+    ///
+    /// // the second item splits one of those again
+    /// packer.push(Item::no_rotation('B', 4, 4));
+    /// packer.pack(Rect::of_size(16, 16)).unwrap();
+    /// assert_eq!(packer.tree_depth(), 2);
+    /// ```
+    pub fn tree_depth(&self) -> usize {
+        match self.strategy {
+            Strategy::Tree => self.scratch.tree.depth(),
+            Strategy::Shelf => 0,
+        }
+    }
+
+    /// How many items were rotated in the last layout, including any added to (or
+    /// removed from) it with [`repack_delta`](Packer::repack_delta).
+    ///
+    /// Dividing this by the number of items packed gives how often rotating items
+    /// helped, such as for deciding if searching for rotated fits is worth the time.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// // only the 3 items lying down need rotating to fit in the narrow container
+    /// let items = (0..5).map(|i| match i < 3 {
+    ///     true => Item::new(i, 8, 2, Rotation::Allowed),
+    ///     false => Item::new(i, 2, 8, Rotation::Allowed),
+    /// });
+    /// let mut packer = Packer::with_items(items);
+    /// let packed = packer.pack(Rect::of_size(4, 24)).unwrap();
+    /// assert_eq!(packer.rotation_count(), 3);
+    /// assert_eq!(packed.iter().filter(|item| item.rotated).count(), 3);
+    ///
+    /// let ratio = packer.rotation_count() as f64 / packed.len() as f64;
+    /// assert_eq!(ratio, 0.6);
+    ///
+    /// // taking a rotated item back out no longer counts it
+    /// let lying = packed.iter().find(|item| item.rotated).unwrap().rect;
+    /// packer.repack_delta(&[], &[lying]).unwrap();
+    /// assert_eq!(packer.rotation_count(), 2);
+    /// ```
+    pub fn rotation_count(&self) -> usize {
+        self.scratch.rotated
+    }
+
+    /// The fraction of `container_area` covered by the items packed in the last layout,
+    /// including any added or removed with [`repack_delta`](Packer::repack_delta) (or
+    /// `0.0` if `container_area` is `0`).
+    ///
+    /// The packed area is added up as items are packed, so this doesn't need to go
+    /// through them all again. To check it while packing is still in progress, use
+    /// [`PackChunks::current_occupancy`].
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect};
+    /// let mut packer = Packer::with_items((0..2).map(|i| Item::no_rotation(i, 8, 8)));
+    /// packer.pack(Rect::of_size(16, 16)).unwrap();
+    /// assert_eq!(packer.current_occupancy(16 * 16), 0.5);
+    /// ```
+    pub fn current_occupancy(&self, container_area: usize) -> f64 {
+        self.scratch.occupancy(container_area)
+    }
+
+    /// The area of the free space left in the last layout, or `0` if nothing has been
+    /// packed with [`Strategy::Tree`].
+    ///
+    /// The tree's free spaces overlap each other, so this counts the area they cover
+    /// between them, rather than adding up their areas. It is updated as items are
+    /// added with [`repack_delta`](Packer::repack_delta).
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let items = [Item::new('A', 8, 8, Rotation::None), Item::new('B', 8, 4, Rotation::None)];
+    /// let mut packer = Packer::with_items(items);
+    /// let packed = packer.pack(Rect::of_size(16, 16)).unwrap();
+    ///
+    /// // the free space left over overlaps, but each pixel is only counted once
+    /// assert_eq!(packer.free_area(), 16 * 16 - 64 - 32);
+    /// let packed_area: usize = packed.iter().map(|i| i.rect.area()).sum();
+    /// assert_eq!(packer.free_area() + packed_area, 16 * 16);
+    /// ```
+    pub fn free_area(&self) -> usize {
+        match self.strategy {
+            Strategy::Tree => self.scratch.tree.free_area(),
+            Strategy::Shelf => 0,
+        }
+    }
+
+    /// Packs the items into `into_rect`, returning the rect each item was packed into
+    /// and whether it was rotated, in the same order the items were added.
+    ///
+    /// None of the item data is returned, which is handy for passing results across
+    /// an FFI boundary. Items that don't fit are skipped, and if there are any, their
+    /// indices are returned in the `Err` instead.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let mut packer = Packer::with_items([
+    ///     Item::new('A', 4, 4, Rotation::None),
+    ///     Item::new('B', 16, 8, Rotation::None),
+    ///     Item::new('C', 8, 4, Rotation::Allowed),
+    /// ]);
+    /// let flat = packer.pack_flat(Rect::of_size(16, 16)).unwrap();
+    /// assert_eq!(flat[0].0.w, 4);
+    /// assert_eq!(flat[1].0.w, 16);
+    /// assert_eq!(flat[2].0.area(), 32);
+    /// assert_eq!(flat[2].1, flat[2].0.w == 4);
+    ///
+    /// packer.push(Item::new('D', 32, 4, Rotation::None));
+    /// packer.push(Item::new('E', 4, 4, Rotation::None));
+    /// assert_eq!(packer.pack_flat(Rect::of_size(16, 16)), Err(vec![3]));
+    /// ```
+    pub fn pack_flat(&mut self, into_rect: Rect) -> Result<Vec<(Rect, bool)>, Vec<usize>> {
+        let flat = self.pack_each(into_rect);
+        match flat.iter().all(Option::is_some) {
+            true => Ok(flat.into_iter().flatten().collect()),
+            false => Err((0..flat.len()).filter(|&i| flat[i].is_none()).collect()),
+        }
+    }
+
+    //pack the items into `into_rect`, returning the rect and rotation of each item in
+    //the order they were added, or `None` for the items that didn't fit
+    pub(crate) fn pack_each(&mut self, into_rect: Rect) -> Vec<Option<(Rect, bool)>> {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let mut flat = vec![None; self.items_to_pack.len()];
+
+        // whyever packing stopped, any items it didn't get to are left as `None`
+        let _ = self.pack_with(&mut scratch, into_rect, true, |item_i, rect| {
+            let rotated = rect.w != self.items_to_pack[item_i].packed_size().0;
+            flat[item_i] = Some((rect, rotated));
+        });
+        self.scratch = scratch;
+        flat
+    }
+
+    //pack the items into `into_rect` using `scratch`, calling `on_packed` with the
+    //index of each item and the rect it was packed into, returning why it stopped if not every item
+    //was packed (or if `keep_going`, adding items that don't fit to `unpacked` instead)
+    fn pack_with<F>(
+        &self,
+        scratch: &mut Scratch,
+        into_rect: Rect,
+        keep_going: bool,
+        mut on_packed: F,
+    ) -> Result<(), Stopped>
+    where
+        F: FnMut(usize, Rect),
+    {
+        let groups = self.sort_order(scratch);
+        if let Some(stopped) = self.pack_lone(scratch, into_rect, keep_going, &mut on_packed) {
+            return stopped;
+        }
+        self.pack_in_order(scratch, &groups, into_rect, keep_going, &mut on_packed)
+    }
+
+    //pack a lone item straight into the top-left of the empty container, since that's
+    //the only free node there is to search, returning `None` if it has to be packed
+    //the usual way (because it's in a group, or can only go in part of the container)
+    fn pack_lone<F>(
+        &self,
+        scratch: &mut Scratch,
+        into_rect: Rect,
+        keep_going: bool,
+        on_packed: &mut F,
+    ) -> Option<Result<(), Stopped>>
+    where
+        F: FnMut(usize, Rect),
+    {
+        let item = match self.items_to_pack.as_slice() {
+            [item] if item.group.is_none() && item.allowed.is_none() => item,
+            _ => return None,
+        };
+        if self.strategy != Strategy::Tree || self.mask.is_some() {
+            return None;
+        }
+        self.start_container(scratch, into_rect);
+
+        // choose the rotation just like the tree does for its root node
+        let pad = self.inter_group_spacing;
+        let space = padded(&into_rect, pad);
+        let (item_w, item_h) = item.packed_size();
+        let w = quantized(item_w, self.size_quantum).saturating_add(pad);
+        let h = quantized(item_h, self.size_quantum).saturating_add(pad);
+        let score = |w, h| match Self::fits(&space, w, h, false) {
+            true => Score::with_heuristic(self.heuristic, &space, w, h),
+            false => Score::worst(),
+        };
+        let (upright, rotated) = (score(w, h), score(h, w));
+        let rotate = self.rotation_of(item) != Rotation::None
+            && w != h
+            && rotated.better_than(&upright)
+            && allows_rotation(&self.rotation_policy, &item.as_ref(), &space);
+        let (w, h, item_w, item_h) = match rotate {
+            true => (h, w, item_h, item_w),
+            false => (w, h, item_w, item_h),
+        };
+        if !Self::fits(&space, w, h, false) {
+            let oversized = self.skip_oversized
+                && !Self::fits(&space, w, h, self.rotation_of(item) != Rotation::None);
+            return Some(match oversized || keep_going || item.optional {
+                true => {
+                    scratch.unpacked.push(0);
+                    Ok(())
+                }
+                false => Err(Stopped::DoesNotFit),
+            });
+        }
+
+        scratch
+            .tree
+            .split_tree(&Rect::new(space.x, space.y, w, h), 0);
+        let rect = Rect::new(into_rect.x, into_rect.y, item_w, item_h);
+        Some(self.add_packed(scratch, 0, rect, on_packed))
+    }
+
+    //like `pack_with`, but packing in the order `sort_order` already put in `scratch`
+    //(which doesn't depend on `into_rect`, so it can be reused for several containers)
+    fn pack_in_order<F>(
+        &self,
+        scratch: &mut Scratch,
+        groups: &[Group],
+        into_rect: Rect,
+        keep_going: bool,
+        on_packed: &mut F,
+    ) -> Result<(), Stopped>
+    where
+        F: FnMut(usize, Rect),
+    {
+        // pack all items, longest sides -> shorted sides
+        self.start_container(scratch, into_rect);
+        for ind in 0..scratch.indices.len() {
+            self.pack_next(scratch, groups, ind, keep_going, on_packed)?;
+        }
+        Ok(())
+    }
+
+    //reset `scratch` to start packing into `into_rect`, packing the groups and sorting
+    //the order to pack everything in, and returning the packed groups
+    fn start_pack(&self, scratch: &mut Scratch, into_rect: Rect) -> Vec<Group> {
+        let groups = self.sort_order(scratch);
+        self.start_container(scratch, into_rect);
+        groups
+    }
+
+    //reset `scratch` to start packing into an empty `into_rect`
+    fn start_container(&self, scratch: &mut Scratch, into_rect: Rect) {
+        // start with one node that is the full size of the rect
+        // reserve a deccent amount of room in the initial nodes vec
+        // (every item or block is padded on its right and bottom to space it from the
+        // others, so the container gets the same padding for ones at its edges)
+        let pad = self.inter_group_spacing;
+        self.reset_scratch(scratch, padded(&into_rect, pad));
+        scratch.packed_into = into_rect;
+        self.apply_mask(scratch);
+    }
+
+    //split the free space around the cells the mask doesn't allow, one run of cells
+    //in a row at a time
+    fn apply_mask(&self, scratch: &mut Scratch) {
+        let mask = match &self.mask {
+            Some(mask) if self.strategy == Strategy::Tree => mask,
+            _ => return,
+        };
+        let (x, y) = scratch.packed_into.top_left();
+        let size = mask.cell_size;
+        for row in 0..mask.rows {
+            let mut col = 0;
+            while col < mask.cols {
+                let allowed = |col| mask.allowed.get(row * mask.cols + col) == Some(&true);
+                if allowed(col) {
+                    col += 1;
+                    continue;
+                }
+                let start = col;
+                while col < mask.cols && !allowed(col) {
+                    col += 1;
+                }
+                let rect = Rect::new(x + start * size, y + row * size, (col - start) * size, size);
+                scratch.tree.split_tree(&rect, 0);
+            }
+        }
+    }
+
+    //pack the groups, and sort the order to pack everything in into `scratch`,
+    //returning the packed groups
+    fn sort_order(&self, scratch: &mut Scratch) -> Vec<Group> {
+        // a lone item (like a glyph being added one at a time) has nothing to be
+        // grouped with or sorted against, so skip straight to placing it
+        if let [item] = self.items_to_pack.as_slice() {
+            if item.group.is_none() {
+                scratch.indices.clear();
+                scratch.indices.push(0);
+                scratch.duplicates.clear();
+                return Vec::new();
+            }
+        }
+
+        // pack each group into its own block first, so it can be placed as one unit
+        let groups = self.pack_groups();
+        let count = self.items_to_pack.len();
+
+        // items sharing a slot with another are packed along with it, not on their own
+        let reps = self.dedup.as_ref().map(|dedup| dedup(&self.items_to_pack));
+        let is_rep = |i: usize| reps.as_ref().map_or(true, |reps| reps[i] == i);
+        scratch.duplicates.clear();
+        if let Some(reps) = &reps {
+            let dups = reps.iter().enumerate().filter(|&(i, &rep)| i != rep);
+            scratch.duplicates.extend(dups.map(|(i, &rep)| (rep, i)));
+            scratch.duplicates.sort_unstable();
+        }
+
+        // indices of items we need to pack, sorted by their area
+        // the largest items should be packed first for best fits
+        // (indices past the last item refer to group blocks)
+        scratch.indices.clear();
+        scratch
+            .indices
+            .extend((0..count).filter(|&i| self.items_to_pack[i].group.is_none() && is_rep(i)));
+        scratch.indices.extend(count..count + groups.len());
+        {
+            let items = &self.items_to_pack;
+            // optional items only get packed after all the required ones
+            let legacy = self.heuristic == Heuristic::LegacyV1;
+            let shaped = self.sort == SortOrder::ShapeGrouped;
+            let shape = |(w, h)| match shaped {
+                true => aspect_bucket(w, h),
+                false => 0,
+            };
+            let priority = |i: usize| match i.checked_sub(count) {
+                Some(group) => {
+                    let group = &groups[group];
+                    (!group.optional, shape((group.w, group.h)), group.priority())
+                }
+                None => {
+                    let item = &items[i];
+                    let priority = match legacy {
+                        true => legacy_v1_priority(item),
+                        false => item.sort_priority(),
+                    };
+                    (!item.optional, shape(item.packed_size()), priority)
+                }
+            };
+            scratch.indices.sort_by_key(|&i| Reverse(priority(i)));
+        }
+
+        groups
+    }
+
+    //pack the `ind`th item (or group block) in the order `sort_order` sorted them into
+    fn pack_next<F>(
+        &self,
+        scratch: &mut Scratch,
+        groups: &[Group],
+        ind: usize,
+        keep_going: bool,
+        on_packed: &mut F,
+    ) -> Result<(), Stopped>
+    where
+        F: FnMut(usize, Rect),
+    {
+        let (into_rect, pad) = (scratch.packed_into, self.inter_group_spacing);
+        let count = self.items_to_pack.len();
+        let item_i = scratch.indices[ind];
+        if let Some(group) = item_i.checked_sub(count).map(|g| &groups[g]) {
+            if self.skip_oversized && !Self::fits(&into_rect, group.w, group.h, false) {
+                let members = group.members.iter().map(|&(member_i, _)| member_i);
+                scratch.unpacked.extend(members);
+                return Ok(());
+            }
+
+            // place the whole block, then each member at its offset inside it
+            let (w, h) = (group.w.saturating_add(pad), group.h.saturating_add(pad));
+            let block = match self.strategy {
+                Strategy::Tree => {
+                    let tiebreak = self.node_tiebreak.as_deref();
+                    scratch.tree.place(w, h, false, None, tiebreak, |_| false)
+                }
+                Strategy::Shelf => scratch.shelves.place(w, h, false, None, |_| false),
+            };
+            let block = match block {
+                Some(block) => block,
+                None if keep_going || group.optional => {
+                    let members = group.members.iter().map(|&(member_i, _)| member_i);
+                    scratch.unpacked.extend(members);
+                    return Ok(());
+                }
+                None => return Err(Stopped::DoesNotFit),
+            };
+            for &(member_i, rect) in &group.members {
+                let rect = Rect::new(block.x + rect.x, block.y + rect.y, rect.w, rect.h);
+                debug_assert_inside(&into_rect, &rect);
+                let rotated = rect.w != self.items_to_pack[member_i].packed_size().0;
+                scratch.placed.push((rect, rotated as usize));
+                scratch.placed_area = scratch.placed_area.saturating_add(rect.area());
+                scratch.rotated += rotated as usize;
+                on_packed(member_i, from_corner(self.origin, &into_rect, rect));
+            }
+            return Ok(());
+        }
+
+        // skip items that wouldn't even fit into the empty container
+        let item = &self.items_to_pack[item_i];
+        if self.skip_oversized {
+            let rotate = self.rotation_of(item) != Rotation::None;
+            let space = match item.allowed {
+                Some(region) => region.intersection(&into_rect),
+                None => Some(into_rect),
+            };
+            let (w, h) = item.packed_size();
+            let (w, h) = (
+                quantized(w, self.size_quantum),
+                quantized(h, self.size_quantum),
+            );
+            if !space.is_some_and(|space| Self::fits(&space, w, h, rotate)) {
+                scratch.unpacked.push(item_i);
+                scratch
+                    .unpacked
+                    .extend(duplicates_of(&scratch.duplicates, item_i));
+                return Ok(());
+            }
+        }
+
+        // if we failed to pack the item, return failure
+        // and everything we did manage to pack
+        let region = item.allowed.map(|region| padded(&region, pad));
+        let rect = match self.place_item(scratch, item, region.as_ref(), pad) {
+            Some(rect) => Rect::new(rect.x, rect.y, rect.w - pad, rect.h - pad),
+            None if keep_going || item.optional => {
+                scratch.unpacked.push(item_i);
+                scratch
+                    .unpacked
+                    .extend(duplicates_of(&scratch.duplicates, item_i));
+                return Ok(());
+            }
+            None => return Err(Stopped::DoesNotFit),
+        };
+
+        self.add_packed(scratch, item_i, rect, on_packed)
+    }
+
+    //add item `item_i`, packed into `rect`, to the successfully packed list
+    fn add_packed<F>(
+        &self,
+        scratch: &mut Scratch,
+        item_i: usize,
+        rect: Rect,
+        on_packed: &mut F,
+    ) -> Result<(), Stopped>
+    where
+        F: FnMut(usize, Rect),
+    {
+        let into_rect = scratch.packed_into;
+        let item = &self.items_to_pack[item_i];
+        trace!(
+            "packed item {} into {:?} (rotated: {})",
+            item_i,
+            rect,
+            rect.w != item.packed_size().0
+        );
+        debug_assert_inside(&into_rect, &rect);
+        // the duplicates share the rect, so removing it takes their rotations with it
+        let copies = 1 + duplicates_of(&scratch.duplicates, item_i).count();
+        let rotations = (rect.w != item.packed_size().0) as usize * copies;
+        scratch.placed.push((rect, rotations));
+        scratch.placed_area = scratch.placed_area.saturating_add(rect.area());
+        scratch.rotated += rotations;
+        let rect = from_corner(self.origin, &into_rect, rect);
+        on_packed(item_i, rect);
+        for dup_i in duplicates_of(&scratch.duplicates, item_i) {
+            on_packed(dup_i, rect);
+        }
+
+        // give up if the free space has split up too much
+        let node_limit = self
+            .max_fragmentation
+            .map(|ratio| (ratio * self.items_to_pack.len() as f64) as usize);
+        if let Some(limit) = node_limit {
+            let nodes = scratch.tree.nodes.len();
+            if self.strategy == Strategy::Tree && nodes > limit {
+                return Err(Stopped::TooFragmented { nodes, limit });
+            }
+        }
+
+        Ok(())
+    }
+
+    //true if a `w` x `h` rect (or `h` x `w` if it can `rotate`) fits inside `space`
+    #[inline]
+    fn fits(space: &Rect, w: usize, h: usize, rotate: bool) -> bool {
+        (w <= space.w && h <= space.h) || (rotate && h <= space.w && w <= space.h)
+    }
+
+    //clear `scratch`, ready to start packing into `into_rect`
+    fn reset_scratch(&self, scratch: &mut Scratch, into_rect: Rect) {
+        let capacity = self
+            .items_to_pack
+            .len()
+            .saturating_mul(self.node_reserve_factor);
+        let (use_index, heuristic) = (self.use_index, self.heuristic);
+        match self.strategy {
+            Strategy::Tree => scratch
+                .tree
+                .reset(into_rect, capacity, use_index, heuristic),
+            Strategy::Shelf => scratch.shelves.clear(into_rect),
+        }
+        scratch.packed_into = into_rect;
+        scratch.placed.clear();
+        scratch.unpacked.clear();
+        scratch.rotated = 0;
+        scratch.placed_area = 0;
+    }
+
+    //find the best position to pack the item, padded by `pad`, inside `region`
+    fn place_item(
+        &self,
+        scratch: &mut Scratch,
+        item: &Item<T>,
+        region: Option<&Rect>,
+        pad: usize,
+    ) -> Option<Rect> {
+        let (item_w, item_h) = item.packed_size();
+        let w = quantized(item_w, self.size_quantum).saturating_add(pad);
+        let h = quantized(item_h, self.size_quantum).saturating_add(pad);
+        let rotate = self.rotation_of(item) != Rotation::None && w != h;
+        let policy = &self.rotation_policy;
+        let item = item.as_ref();
+        let allow = |rect: &Rect| allows_rotation(policy, &item, rect);
+        let rect = match self.strategy {
+            Strategy::Tree => {
+                let tiebreak = self.node_tiebreak.as_deref();
+                scratch.tree.place(w, h, rotate, region, tiebreak, allow)
+            }
+            Strategy::Shelf => scratch.shelves.place(w, h, rotate, region, allow),
+        }?;
+
+        // the item only covers the top-left of the (quantized) space reserved for it
+        let (item_w, item_h) = match rect.w == w {
+            true => (item_w, item_h),
+            false => (item_h, item_w),
+        };
+        Some(Rect::new(
+            rect.x,
+            rect.y,
+            item_w.saturating_add(pad),
+            item_h.saturating_add(pad),
+        ))
+    }
+
+    //pack the members of every group into their own tightly fitting blocks
+    fn pack_groups(&self) -> Vec<Group> {
+        let mut ids: Vec<u32> = self.items_to_pack.iter().filter_map(|i| i.group).collect();
+        if ids.is_empty() {
+            return Vec::new();
+        }
+        ids.sort_unstable();
+        ids.dedup();
+
+        let mut scratch = Scratch::new();
+        ids.into_iter()
+            .map(|id| self.pack_group(&mut scratch, id))
+            .collect()
+    }
+
+    //pack the members of group `id` into the smallest square we can find, and then
+    //shrink the block down to the bounds of what was packed
+    fn pack_group(&self, scratch: &mut Scratch, id: u32) -> Group {
+        let mut order: Vec<usize> = (0..self.items_to_pack.len())
+            .filter(|&i| self.items_to_pack[i].group == Some(id))
+            .collect();
+        let items = &self.items_to_pack;
+        order.sort_by(|&a, &b| items[b].sort_priority().cmp(&items[a].sort_priority()));
+
+        let sizes = || order.iter().map(|&i| items[i].packed_size());
+        let area = sizes().fold(0, |area: usize, (w, h)| {
+            area.saturating_add(w.saturating_mul(h))
+        });
+        let longest = sizes().map(|(w, h)| w.max(h)).max();
+        let mut side = ceil_sqrt(area).max(longest.unwrap_or(0));
+        let mut members = Vec::with_capacity(order.len());
+        loop {
+            self.reset_scratch(scratch, Rect::of_size(side, side));
+            members.clear();
+            for &i in &order {
+                match self.place_item(scratch, &items[i], None, 0) {
+                    Some(rect) => members.push((i, rect)),
+                    None => break,
+                }
+            }
+            if members.len() == order.len() {
+                break;
+            }
+            if side == usize::MAX {
+                //the members don't fit even the largest square, so the group can't fit
+                //anything, and reporting it as that large makes sure it never gets placed
+                let members = order.iter().map(|&i| (i, Rect::default())).collect();
+                let optional = order.iter().all(|&i| items[i].optional);
+                return Group {
+                    w: usize::MAX,
+                    h: usize::MAX,
+                    members,
+                    optional,
+                };
+            }
+            side = side.saturating_add((side / 16).max(1));
+        }
+
+        let w = members.iter().map(|(_, r)| r.right()).max().unwrap_or(0);
+        let h = members.iter().map(|(_, r)| r.bottom()).max().unwrap_or(0);
+        let optional = order.iter().all(|&i| items[i].optional);
+        Group {
+            w,
+            h,
+            members,
+            optional,
+        }
+    }
+
+    /// Checks the queued items for mistakes that packing would silently accept.
+    ///
+    /// This catches items with a zero width or height (which take up no space, so
+    /// they all get packed on top of each other), and items so large their area
+    /// overflows a `usize`. It is not called by `pack`, so call it yourself if your
+    /// item sizes come from an untrusted source.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, ValidationError};
+    /// let mut packer = Packer::new();
+    /// packer.push(Item::new('A', 10, 10, Rotation::None));
+    /// assert_eq!(packer.validate(), Ok(()));
+    ///
+    /// packer.push(Item::new('B', 0, 10, Rotation::None));
+    /// assert_eq!(packer.validate(), Err(ValidationError::ZeroSized { index: 1 }));
+    ///
+    /// packer.clear().push(Item::new('C', usize::MAX, 2, Rotation::None));
+    /// assert_eq!(packer.validate(), Err(ValidationError::TooLarge { index: 0 }));
+    /// ```
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        for (index, item) in self.items_to_pack.iter().enumerate() {
+            if item.w == 0 || item.h == 0 || item.scale == 0 {
+                return Err(ValidationError::ZeroSized { index });
+            }
+            let scale = item.scale as usize;
+            let w = item.w.checked_mul(scale);
+            let h = item.h.checked_mul(scale);
+            if w.zip(h).and_then(|(w, h)| w.checked_mul(h)).is_none() {
+                return Err(ValidationError::TooLarge { index });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every item in `packed` lies inside `into_rect`, and that no two of
+    /// them overlap, such as to check the results of [`pack`](Packer::pack).
+    ///
+    /// The items are swept through from left to right, so each is only compared with
+    /// the ones it spans horizontally, rather than every other item. Items with a zero
+    /// width or height don't cover any area, so they never overlap.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect, ValidationError};
+    /// // four squares tile the container exactly
+    /// let items = ['A', 'B', 'C', 'D'].map(|c| Item::new(c, 8, 8, Rotation::None));
+    /// let mut packer = Packer::with_items(items);
+    /// let rect = Rect::of_size(16, 16);
+    /// let mut packed = packer.pack(rect).unwrap();
+    /// assert_eq!(packer.verify_last_pack(&packed, &rect), Ok(()));
+    ///
+    /// // move the last one on top of the first
+    /// packed[3].rect = packed[0].rect;
+    /// assert_eq!(
+    ///     packer.verify_last_pack(&packed, &rect),
+    ///     Err(ValidationError::Overlapping { first: 0, second: 3 })
+    /// );
+    ///
+    /// // and then out of the container entirely
+    /// packed[3].rect.x = 16;
+    /// assert_eq!(
+    ///     packer.verify_last_pack(&packed, &rect),
+    ///     Err(ValidationError::OutOfBounds { index: 3 })
+    /// );
+    /// ```
+    pub fn verify_last_pack(
+        &self,
+        packed: &[PackedItem<T>],
+        into_rect: &Rect,
+    ) -> Result<(), ValidationError> {
+        let inside = |rect: &Rect| {
+            rect.x >= into_rect.x
+                && rect.y >= into_rect.y
+                && rect.right() <= into_rect.right()
+                && rect.bottom() <= into_rect.bottom()
+        };
+        if let Some(index) = packed.iter().position(|item| !inside(&item.rect)) {
+            return Err(ValidationError::OutOfBounds { index });
+        }
+
+        // sweep from left to right, keeping the items the sweep line is crossing
+        let mut order: Vec<usize> = (0..packed.len())
+            .filter(|&i| !packed[i].rect.is_empty())
+            .collect();
+        order.sort_by_key(|&i| packed[i].rect.x);
+        let mut crossing: Vec<usize> = Vec::new();
+        for i in order {
+            let rect = &packed[i].rect;
+            crossing.retain(|&j| packed[j].rect.right() > rect.x);
+            if let Some(&j) = crossing.iter().find(|&&j| packed[j].rect.overlaps(rect)) {
+                let (first, second) = (i.min(j), i.max(j));
+                return Err(ValidationError::Overlapping { first, second });
+            }
+            crossing.push(i);
+        }
+        Ok(())
     }
 }
 
-/// A branch of the packing tree, `split` are indices that point to other nodes.
-struct Node {
-    rect: Rect,
-    is_split: bool,
-    split: [usize; 4],
+impl<T: ?Sized> Packer<&T> {
+    /// Checks that no two queued items refer to the same data.
+    ///
+    /// When packing references, the same reference showing up twice usually means
+    /// the item list was built incorrectly, and the data would end up being placed
+    /// in the container twice.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, ValidationError};
+    /// let (a, b) = (String::from("a"), String::from("b"));
+    /// let mut packer = Packer::new();
+    /// packer.push(Item::new(&a, 10, 10, Rotation::None));
+    /// packer.push(Item::new(&b, 10, 10, Rotation::None));
+    /// assert_eq!(packer.validate_unique_data(), Ok(()));
+    ///
+    /// packer.push(Item::new(&a, 20, 20, Rotation::None));
+    /// assert_eq!(
+    ///     packer.validate_unique_data(),
+    ///     Err(ValidationError::DuplicateData { first: 0, second: 2 })
+    /// );
+    /// ```
+    pub fn validate_unique_data(&self) -> Result<(), ValidationError> {
+        let mut seen = HashMap::with_capacity(self.items_to_pack.len());
+        for (second, item) in self.items_to_pack.iter().enumerate() {
+            if let Some(&first) = seen.get(&(item.data as *const T as *const ())) {
+                return Err(ValidationError::DuplicateData { first, second });
+            }
+            seen.insert(item.data as *const T as *const (), second);
+        }
+        Ok(())
+    }
 }
 
-/// The packer's way of scoring how well a rect fits into another rect.
-#[derive(Copy, Clone)]
-struct Score {
-    area_fit: usize,
-    short_fit: usize,
+/// Packs a [`Packer`]'s items a chunk at a time, see [`Packer::pack_chunked`].
+pub struct PackChunks<'a, T> {
+    packer: &'a mut Packer<T>,
+    scratch: Scratch,
+    groups: Vec<Group>,
+    chunk: usize,
+    next: usize,
+    failed: bool,
+    packed: Vec<PackedItem<T>>,
 }
 
-impl Score {
-    /// Score how well `rect` fits into a rect of size `w` x `h`.
-    #[inline]
-    fn new(rect: &Rect, w: usize, h: usize) -> Self {
-        let extra_x = rect.w - w;
-        let extra_y = rect.h - h;
-        Self {
-            area_fit: rect.area() - w * h,
-            short_fit: extra_x.min(extra_y),
+impl<T: Clone> PackChunks<'_, T> {
+    /// Packs the next chunk of items, returning `true` if there are still more left
+    /// to pack (so `false` once every item is packed, or one didn't fit).
+    pub fn step(&mut self) -> bool {
+        let end = (self.next + self.chunk).min(self.scratch.indices.len());
+        while !self.failed && self.next < end {
+            let (packer, packed) = (&*self.packer, &mut self.packed);
+            let mut on_packed = |item_i: usize, rect| {
+                packed.push(Packer::packed_item(&packer.items_to_pack[item_i], rect))
+            };
+            let stopped = packer.pack_next(
+                &mut self.scratch,
+                &self.groups,
+                self.next,
+                false,
+                &mut on_packed,
+            );
+            self.failed = stopped.is_err();
+            self.next += 1;
         }
+        !self.failed && self.next < self.scratch.indices.len()
     }
 
-    /// The worst possible packing score.
-    #[inline]
-    const fn worst() -> Self {
-        Self {
-            area_fit: usize::MAX,
-            short_fit: usize::MAX,
+    /// The fraction of `container_area` covered by the items packed so far, such as
+    /// for showing how full the container is getting.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect};
+    /// let mut packer = Packer::with_items((0..4).map(|i| Item::no_rotation(i, 8, 8)));
+    /// let mut chunks = packer.pack_chunked(Rect::of_size(16, 16), 2);
+    /// assert_eq!(chunks.current_occupancy(16 * 16), 0.0);
+    ///
+    /// // half of the items are packed after the first step
+    /// chunks.step();
+    /// assert_eq!(chunks.current_occupancy(16 * 16), 0.5);
+    /// chunks.step();
+    /// assert_eq!(chunks.current_occupancy(16 * 16), 1.0);
+    /// ```
+    pub fn current_occupancy(&self, container_area: usize) -> f64 {
+        self.scratch.occupancy(container_area)
+    }
+
+    /// Packs all the items that are left, and returns the same result
+    /// [`pack`](Packer::pack) would have.
+    pub fn finish(mut self) -> Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>> {
+        while self.step() {}
+        let packed = std::mem::take(&mut self.packed);
+        match self.failed {
+            true => Err(packed),
+            false => Ok(packed),
         }
     }
+}
+
+impl<T> Drop for PackChunks<'_, T> {
+    //give the intermediary data back to the packer, like `pack` does
+    fn drop(&mut self) {
+        self.packer.scratch = std::mem::take(&mut self.scratch);
+    }
+}
+
+impl<T> Default for Packer<T> {
+    /// Default packer, equivalent to `Packer::new()`.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Two packers are equal if they have the same items queued, in the same order.
+///
+/// Only the queued items are compared, not the settings they'll be packed with, or
+/// anything left over from packing them before.
+///
+/// ```
+/// # use crunch::{Packer, Item, Rotation, Rect};
+/// let items = (0..4).map(|i| Item::new(i, 8, 4, Rotation::Allowed));
+/// let mut a = Packer::with_items(items.clone());
+/// let mut b = Packer::new();
+/// b.extend(items);
+/// assert!(a == b);
+///
+/// // packing doesn't change the queued items
+/// a.pack(Rect::of_size(16, 16)).unwrap();
+/// assert!(a == b);
+///
+/// b.push(Item::new(4, 8, 4, Rotation::Allowed));
+/// assert!(a != b);
+/// ```
+impl<T: PartialEq> PartialEq for Packer<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.items_to_pack == other.items_to_pack
+    }
+}
+
+impl<T: Clone> Packer<T> {
+    /// Remove all queued items.
+    ///
+    /// The memory used for the items and for packing is kept, so the packer can
+    /// be reused without allocating again. To release it, use
+    /// [`clear_and_shrink`](Packer::clear_and_shrink).
+    pub fn clear(&mut self) -> &mut Self {
+        self.items_to_pack.clear();
+        self.prepared = None;
+        self
+    }
+
+    /// Remove all queued items, and forget the last layout (so there is nothing left
+    /// for [`repack_delta`](Packer::repack_delta) or [`unpacked`](Packer::unpacked)),
+    /// ready to pack a new batch of items with the same settings.
+    ///
+    /// Like [`clear`](Packer::clear), the memory used is kept.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let mut packer = Packer::new().with_rotation_override(Some(Rotation::None));
+    /// packer.push(Item::new('A', 8, 8, Rotation::None));
+    /// packer.pack(Rect::of_size(16, 16)).unwrap();
+    ///
+    /// packer.reset();
+    /// assert!(packer.is_empty());
+    /// assert_eq!(packer.tree_depth(), 0);
+    ///
+    /// // the rotation override is still used, so the item can't be rotated to fit
+    /// packer.push(Item::new('B', 4, 16, Rotation::Allowed));
+    /// assert!(packer.pack(Rect::of_size(16, 8)).is_err());
+    /// ```
+    pub fn reset(&mut self) -> &mut Self {
+        self.items_to_pack.clear();
+        self.scratch.clear();
+        self.prepared = None;
+        self
+    }
+
+    /// Remove all queued items, and release all the memory used for them and for
+    /// packing, as well as the layout used by [`repack_delta`](Packer::repack_delta).
+    ///
+    /// This is useful for a long-lived packer that has just packed a lot of items,
+    /// and isn't expected to pack that many again.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let mut packer = Packer::with_items((0..1000).map(|i| Item::new(i, 1, 1, Rotation::None)));
+    /// packer.pack(Rect::of_size(100, 100)).unwrap();
+    ///
+    /// packer.clear_and_shrink();
+    /// assert!(packer.is_empty());
+    /// ```
+    pub fn clear_and_shrink(&mut self) -> &mut Self {
+        self.items_to_pack.clear();
+        self.items_to_pack.shrink_to_fit();
+        self.scratch = Scratch::new();
+        self.prepared = None;
+        self
+    }
+
+    #[inline]
+    pub fn push(&mut self, item: Item<T>) -> &mut Self {
+        self.items_to_pack.push(item);
+        self.prepared = None;
+        self
+    }
 
-    /// Returns `true` if this score is better than `other`.
     #[inline]
-    const fn better_than(&self, other: &Score) -> bool {
-        self.area_fit < other.area_fit
-            || (self.area_fit == other.area_fit && self.short_fit < other.short_fit)
+    pub fn extend<I: IntoIterator<Item = Item<T>>>(&mut self, items: I) -> &mut Self {
+        self.items_to_pack.extend(items);
+        self.prepared = None;
+        self
+    }
+
+    /// Like [`pack`](Packer::pack), but on failure, returns a [`PackError`] saying why
+    /// packing stopped, along with the items that were packed.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect, PackError};
+    /// let mut packer = Packer::with_items([Item::no_rotation('A', 8, 8), Item::no_rotation('B', 8, 8)]);
+    /// match packer.try_pack(Rect::of_size(8, 8)) {
+    ///     Err(PackError::DoesNotFit(packed)) => assert_eq!(packed.len(), 1),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn try_pack(&mut self, into_rect: Rect) -> Result<Vec<PackedItem<T>>, PackError<T>> {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let result = self.pack_cloned_with(&mut scratch, into_rect, None);
+        self.scratch = scratch;
+        result
+    }
+
+    /// Attempt to pack all the items into `into_rect`. The returned `Vec<(Rect, T)>`
+    /// will contain positions for all packed items on success, or just the items
+    /// the packer was able to successfully pack before failing.
+    ///
+    /// This function uses some internal intermediary collections, which is why
+    /// it is mutable, so it cannot be called but it is valid to call it multiple times with different
+    /// `into_rect` values.
+    ///
+    /// If you want to attempt to pack the same item list into several different
+    /// `into_rect`, it is valid to call this function multiple times on the same
+    /// `Packer`, and it will re-use its intermediary data structures.
+    ///
+    /// Every packed item lies inside `into_rect`, even when it was rotated to fit
+    /// against the container's edges (which debug builds also check as they pack).
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// // the tall item only fits lying down along the top of the container
+    /// let into_rect = Rect::new(3, 5, 12, 8);
+    /// let items = [
+    ///     Item::new('A', 4, 12, Rotation::Allowed),
+    ///     Item::new('B', 8, 4, Rotation::Allowed),
+    /// ];
+    /// let mut packer = Packer::with_items(items);
+    /// let packed = packer.pack(into_rect).unwrap();
+    /// assert_eq!((packed[0].rect, packed[0].rotated), (Rect::new(3, 5, 12, 4), true));
+    /// assert_eq!(packed[1].rect, Rect::new(3, 9, 8, 4));
+    /// assert!(packed.iter().all(|item| into_rect.contains(&item.rect)));
+    /// ```
+    ///
+    /// A single item goes straight into the top-left of the container, without any of
+    /// the sorting, grouping or searching for space needed for more, but it's placed
+    /// exactly where it would be among other items.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let rect = Rect::new(2, 3, 12, 20);
+    /// let item = Item::new('A', 16, 10, Rotation::Allowed);
+    /// let alone = Packer::with_items([item.clone()]).pack(rect).unwrap();
+    ///
+    /// // packed after it, a tiny item can't change where it goes
+    /// let mut tiny = Item::new('B', 1, 1, Rotation::None);
+    /// tiny.sort_override = Some(0);
+    /// let together = Packer::with_items([item, tiny]).pack(rect).unwrap();
+    ///
+    /// assert_eq!(alone[0].rect, together[0].rect);
+    /// assert!(alone[0].rotated && together[0].rotated);
+    /// ```
+    pub fn pack(&mut self, into_rect: Rect) -> Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>> {
+        self.try_pack(into_rect).map_err(PackError::into_packed)
+    }
+
+    /// Like [`pack`](Packer::pack), but first tries packing without rotating any items,
+    /// and only if they don't all fit, packs them again with their own rotation settings.
+    ///
+    /// Packing without rotation is faster, since rotated fits don't need searching for,
+    /// so this is quicker whenever the items fit without rotating. But when they don't,
+    /// the items are packed twice, which costs more than packing them once with `pack`
+    /// would have.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let items = (0..2).map(|i| Item::new(i, 4, 10, Rotation::Allowed));
+    /// let mut packer = Packer::with_items(items);
+    ///
+    /// // the items fit a tall container without rotating
+    /// let packed = packer.pack_fast_then_rotate(Rect::of_size(8, 10)).unwrap();
+    /// assert!(packed.iter().all(|item| !item.rotated));
+    ///
+    /// // but they only fit a wide one rotated, so they're packed again to rotate them
+    /// let packed = packer.pack_fast_then_rotate(Rect::of_size(10, 8)).unwrap();
+    /// assert!(packed.iter().all(|item| item.rotated));
+    /// ```
+    pub fn pack_fast_then_rotate(
+        &mut self,
+        into_rect: Rect,
+    ) -> Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>> {
+        // the prepared order belongs to the items' own settings, so keep it for after
+        let (rotation, prepared) = (self.rotation_override, self.prepared.take());
+        self.rotation_override = Some(Rotation::None);
+        let fast = self.pack(into_rect);
+        self.rotation_override = rotation;
+        self.prepared = prepared;
+
+        // if nothing could have rotated anyway, packing again would change nothing
+        let mut items = self.items_to_pack.iter();
+        match fast {
+            Err(_) if items.any(|item| self.rotation_of(item) != Rotation::None) => {
+                self.pack(into_rect)
+            }
+            fast => fast,
+        }
+    }
+
+    /// Like [`pack`](Packer::pack), but first rounds `into_rect`'s width and height up
+    /// to the nearest multiple of `multiple`, and returns the rounded container along
+    /// with the packed items.
+    ///
+    /// This is for targets like block-compressed texture formats, which need their
+    /// dimensions to be a multiple of 4. The items are packed into the whole rounded
+    /// container, so any extra space is just left unused.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let items = [Item::new('A', 10, 6, Rotation::None), Item::new('B', 6, 6, Rotation::None)];
+    /// let mut packer = Packer::with_items(items);
+    ///
+    /// // side by side the items take up 16x6, so they only fit once 13x5 is rounded up
+    /// let packed = packer.pack_rounded(Rect::of_size(13, 5), 4).unwrap();
+    /// assert_eq!((packed.w, packed.h), (16, 8));
+    /// assert_eq!(packed.items[1].rect, Rect::new(10, 0, 6, 6));
+    ///
+    /// // sizes that are already multiples aren't changed
+    /// let packed = packer.pack_rounded(Rect::of_size(16, 8), 4).unwrap();
+    /// assert_eq!((packed.w, packed.h), (16, 8));
+    /// ```
+    pub fn pack_rounded(
+        &mut self,
+        into_rect: Rect,
+        multiple: usize,
+    ) -> Result<PackedItems<T>, PackedItems<T>> {
+        let multiple = multiple.max(1);
+        let (w, h) = (
+            quantized(into_rect.w, multiple),
+            quantized(into_rect.h, multiple),
+        );
+        match self.pack(Rect::new(into_rect.x, into_rect.y, w, h)) {
+            Ok(items) => Ok(PackedItems { w, h, items }),
+            Err(items) => Err(PackedItems { w, h, items }),
+        }
+    }
+
+    /// Sort the items into the order they're packed in, and keep that order to be
+    /// reused by every [`pack_prepared`](Packer::pack_prepared) call.
+    ///
+    /// Sorting (and packing any groups) doesn't depend on the container, so when
+    /// packing the same items into many different containers, this saves redoing it
+    /// for each one. Adding or removing items, or changing the rotation, strategy,
+    /// heuristic, sort order, or tiebreak settings, forgets the order again.
+    pub fn prepare(&mut self) -> &mut Self {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let groups = self.sort_order(&mut scratch);
+        let indices = scratch.indices.clone();
+        let duplicates = scratch.duplicates.clone();
+        self.scratch = scratch;
+        self.prepared = Some(Prepared {
+            indices,
+            duplicates,
+            groups,
+        });
+        self
+    }
+
+    /// Like [`pack`](Packer::pack), but packs the items in the order already sorted
+    /// by [`prepare`](Packer::prepare), without sorting them again. If the order isn't
+    /// prepared (or has been forgotten), it is prepared first.
+    ///
+    /// The results are exactly the same as `pack`'s.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let items = [
+    ///     Item::new('C', 4, 4, Rotation::None),
+    ///     Item::new('A', 8, 8, Rotation::None),
+    ///     Item::new('B', 8, 4, Rotation::None),
+    /// ];
+    /// let mut packer = Packer::with_items(items);
+    /// packer.prepare();
+    ///
+    /// // the order is sorted once, and reused for every container tried
+    /// assert!(packer.pack_prepared(Rect::of_size(8, 8)).is_err());
+    /// let prepared = packer.pack_prepared(Rect::of_size(16, 8)).unwrap();
+    /// let layout: Vec<_> = prepared.iter().map(|item| (item.data, item.rect.into())).collect();
+    /// assert_eq!(layout, [('A', (0, 0, 8, 8)), ('B', (8, 0, 8, 4)), ('C', (8, 4, 4, 4))]);
+    ///
+    /// // which is exactly where `pack` puts them
+    /// let packed = packer.pack(Rect::of_size(16, 8)).unwrap();
+    /// assert!(prepared.iter().zip(&packed).all(|(a, b)| (a.data, a.rect) == (b.data, b.rect)));
+    /// ```
+    pub fn pack_prepared(
+        &mut self,
+        into_rect: Rect,
+    ) -> Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>> {
+        if self.prepared.is_none() {
+            self.prepare();
+        }
+        #[cfg(test)]
+        PACK_PREPARED_CALLS.with(|calls| calls.set(calls.get() + 1));
+        let prepared = self.prepared.take().unwrap();
+        let mut scratch = std::mem::take(&mut self.scratch);
+        scratch.indices.clone_from(&prepared.indices);
+        scratch.duplicates.clone_from(&prepared.duplicates);
+        let result = self.pack_cloned_with(&mut scratch, into_rect, Some(&prepared.groups));
+        self.scratch = scratch;
+        self.prepared = Some(prepared);
+        result.map_err(PackError::into_packed)
+    }
+
+    /// Like [`pack`](Packer::pack), but instead of packing everything at once, returns
+    /// a [`PackChunks`] that packs `chunk` items every time it is
+    /// [`step`](PackChunks::step)ped.
+    ///
+    /// This lets a long pack be spread out over several frames (or between other
+    /// work), without blocking until it is done. Groups of items count as one item.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let tiles = (0..6).map(|i| Item::new(i, 8, 8, Rotation::None));
+    /// let mut packer = Packer::with_items(tiles);
+    /// let packed = packer.pack(Rect::of_size(24, 16)).unwrap();
+    ///
+    /// // two tiles are packed each step, so the last of the three steps finishes
+    /// let mut chunks = packer.pack_chunked(Rect::of_size(24, 16), 2);
+    /// assert!(chunks.step());
+    /// assert_eq!(chunks.current_occupancy(24 * 16), 1.0 / 3.0);
+    /// assert!(chunks.step());
+    /// assert!(!chunks.step());
+    ///
+    /// // which ends up exactly where packing all at once puts them
+    /// let chunked = chunks.finish().unwrap();
+    /// assert_eq!(chunked[5].rect, Rect::new(16, 8, 8, 8));
+    /// assert!(packed.iter().zip(&chunked).all(|(a, b)| (a.data, a.rect) == (b.data, b.rect)));
+    /// ```
+    pub fn pack_chunked(&mut self, into_rect: Rect, chunk: usize) -> PackChunks<'_, T> {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let groups = self.start_pack(&mut scratch, into_rect);
+        let packed = Vec::with_capacity(self.items_to_pack.len());
+        PackChunks {
+            packer: self,
+            scratch,
+            groups,
+            chunk: chunk.max(1),
+            next: 0,
+            failed: false,
+            packed,
+        }
+    }
+
+    /// Like [`pack`](Packer::pack), but the returned items are sorted in reading
+    /// order (top-to-bottom, then left-to-right) by the top-left of their rects,
+    /// instead of the order they were packed in.
+    ///
+    /// This only changes the order of the output, not how the items are packed.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect};
+    /// let mut packer = Packer::with_items((0..8).map(|i| Item::no_rotation(i, 4 + i, 4)));
+    /// let packed = packer.pack_sorted(Rect::of_size(32, 32)).unwrap();
+    /// assert_eq!(packed.len(), 8);
+    ///
+    /// let corners: Vec<_> = packed.iter().map(|p| (p.rect.y, p.rect.x)).collect();
+    /// let mut sorted = corners.clone();
+    /// sorted.sort();
+    /// assert_eq!(corners, sorted);
+    /// ```
+    pub fn pack_sorted(
+        &mut self,
+        into_rect: Rect,
+    ) -> Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>> {
+        let sort = |mut packed: Vec<PackedItem<T>>| {
+            packed.sort_by_key(|item| (item.rect.y, item.rect.x));
+            packed
+        };
+        self.pack(into_rect).map(sort).map_err(sort)
+    }
+
+    /// Like [`pack`](Packer::pack), but without using or changing the packer's own
+    /// intermediary data, so it only needs to borrow the packer.
+    ///
+    /// Each item's data is cloned at most once, and only if the item was packed.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// static CLONES: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// #[derive(Debug)]
+    /// struct Counted;
+    /// impl Clone for Counted {
+    ///     fn clone(&self) -> Self {
+    ///         CLONES.fetch_add(1, Ordering::Relaxed);
+    ///         Counted
+    ///     }
+    /// }
+    ///
+    /// // only 4 of the 6 items fit, so only those 4 get cloned
+    /// let packer = Packer::with_items((0..6).map(|_| Item::new(Counted, 8, 8, Rotation::None)));
+    /// let packed = packer.pack_cloned(Rect::of_size(16, 16)).unwrap_err();
+    /// assert_eq!(packed.len(), 4);
+    /// assert_eq!(CLONES.load(Ordering::Relaxed), 4);
+    /// ```
+    pub fn pack_cloned(&self, into_rect: Rect) -> Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>> {
+        self.pack_cloned_with(&mut Scratch::new(), into_rect, None)
+            .map_err(PackError::into_packed)
+    }
+
+    //pack into `into_rect` using `scratch`, cloning the data of every packed item
+    //(in the order already sorted into `scratch` for `groups`, if given)
+    fn pack_cloned_with(
+        &self,
+        scratch: &mut Scratch,
+        into_rect: Rect,
+        groups: Option<&[Group]>,
+    ) -> Result<Vec<PackedItem<T>>, PackError<T>> {
+        // list of packed items we'll return (whether we succeed or fail)
+        let mut packed = Vec::with_capacity(self.items_to_pack.len());
+        let mut on_packed =
+            |item_i: usize, rect| packed.push(Self::packed_item(&self.items_to_pack[item_i], rect));
+        let stopped = match groups {
+            Some(groups) => self.pack_in_order(scratch, groups, into_rect, false, &mut on_packed),
+            None => self.pack_with(scratch, into_rect, false, on_packed),
+        };
+        match stopped {
+            Ok(()) => Ok(packed),
+            Err(Stopped::DoesNotFit) => Err(PackError::DoesNotFit(packed)),
+            Err(Stopped::TooFragmented { nodes, limit }) => Err(PackError::TooFragmented {
+                packed,
+                nodes,
+                limit,
+            }),
+        }
+    }
+
+    /// Like [`pack`](Packer::pack), but items that don't fit are left out rather than
+    /// stopping the pack, and it only fails if fewer than `min_required` items were
+    /// packed (or the free space split up more than
+    /// [`with_max_fragmentation`](Packer::with_max_fragmentation) allows).
+    ///
+    /// This is for when some of the items are best-effort, but too few of them being
+    /// packed makes the result useless. The items left out can be found with
+    /// [`unpacked`](Packer::unpacked). Either way, every item that was packed is
+    /// returned.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect};
+    /// // only 5 of the 10 items fit
+    /// let mut packer = Packer::with_items((0..10).map(|i| Item::no_rotation(i, 8, 8)));
+    /// let rect = Rect::of_size(40, 8);
+    ///
+    /// let packed = packer.pack_min(rect, 6).unwrap_err();
+    /// assert_eq!(packed.len(), 5);
+    ///
+    /// let packed = packer.pack_min(rect, 5).unwrap();
+    /// assert_eq!(packed.len(), 5);
+    /// assert_eq!(packer.unpacked().count(), 5);
+    /// ```
+    pub fn pack_min(
+        &mut self,
+        into_rect: Rect,
+        min_required: usize,
+    ) -> Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>> {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let mut packed = Vec::with_capacity(self.items_to_pack.len());
+        let stopped = self.pack_with(&mut scratch, into_rect, true, |item_i, rect| {
+            packed.push(Self::packed_item(&self.items_to_pack[item_i], rect))
+        });
+        self.scratch = scratch;
+
+        match stopped.is_ok() && packed.len() >= min_required {
+            true => Ok(packed),
+            false => Err(packed),
+        }
+    }
+
+    /// Packs the items up to `attempts` times, first in the usual order, and then in
+    /// shuffled orders, returning the first attempt that packs every item, or if none
+    /// do, the one that packed the most area.
+    ///
+    /// Shuffling sometimes finds a layout the usual largest-first order doesn't, at the
+    /// cost of packing again for each attempt. Required items are still all packed
+    /// before [optional](Item::optional) ones. The orders are shuffled by a generator
+    /// with a fixed seed, so the same items always give the same result. To shuffle
+    /// with your own random numbers, use [`pack_best_of_with`](Packer::pack_best_of_with).
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// // these items tile the container exactly, but not when packed largest first
+    /// let items = [('A', 3, 4), ('B', 1, 5), ('C', 1, 4), ('D', 4, 1)];
+    /// let items = items.map(|(data, w, h)| Item::new(data, w, h, Rotation::None));
+    /// let mut packer = Packer::with_items(items);
+    /// let rect = Rect::of_size(5, 5);
+    /// assert!(packer.pack(rect).is_err());
+    ///
+    /// let best = packer.pack_best_of(rect, 10).unwrap();
+    /// assert_eq!(best.iter().map(|item| item.rect.area()).sum::<usize>(), 25);
+    /// ```
+    pub fn pack_best_of(
+        &mut self,
+        into_rect: Rect,
+        attempts: usize,
+    ) -> Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>> {
+        // xorshift64, which is plenty random enough for shuffling
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        self.pack_best_of_with(into_rect, attempts, move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        })
+    }
+
+    /// Like [`pack_best_of`](Packer::pack_best_of), but shuffles the orders with the
+    /// random numbers returned by `next_rand`, such as from a generator seeded by the
+    /// rest of your app.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let items = [('A', 3, 4), ('B', 1, 5), ('C', 1, 4), ('D', 4, 1)];
+    /// let items = items.map(|(data, w, h)| Item::new(data, w, h, Rotation::None));
+    /// let mut packer = Packer::with_items(items);
+    /// let rect = Rect::of_size(5, 5);
+    /// let rects = |packed: Vec<crunch::PackedItem<char>>| -> Vec<_> {
+    ///     packed.into_iter().map(|item| (item.data, item.rect)).collect()
+    /// };
+    ///
+    /// // the same random numbers always pick the same layout
+    /// let sequence = [7, 3, 99, 12, 5, 64, 1, 42];
+    /// let fixed = || {
+    ///     let mut numbers = sequence.iter().copied().cycle();
+    ///     move || numbers.next().unwrap()
+    /// };
+    /// let (Ok(a) | Err(a)) = packer.pack_best_of_with(rect, 8, fixed());
+    /// let (Ok(b) | Err(b)) = packer.pack_best_of_with(rect, 8, fixed());
+    /// assert_eq!(rects(a), rects(b));
+    ///
+    /// // and a single attempt is the same as `pack`
+    /// let (Ok(once) | Err(once)) = packer.pack_best_of_with(rect, 1, || unreachable!());
+    /// let (Ok(packed) | Err(packed)) = packer.pack(rect);
+    /// assert_eq!(rects(once), rects(packed));
+    /// ```
+    pub fn pack_best_of_with<R: FnMut() -> u64>(
+        &mut self,
+        into_rect: Rect,
+        attempts: usize,
+        mut next_rand: R,
+    ) -> Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>> {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let groups = self.sort_order(&mut scratch);
+
+        // the required items come first, so shuffle them separately from the rest
+        let count = self.items_to_pack.len();
+        let is_optional = |i: usize| match i.checked_sub(count) {
+            Some(group) => groups[group].optional,
+            None => self.items_to_pack[i].optional,
+        };
+        let required = scratch
+            .indices
+            .iter()
+            .take_while(|&&i| !is_optional(i))
+            .count();
+
+        let attempts = attempts.max(1);
+        let (mut best, mut best_order, mut best_packed) = (None, Vec::new(), Vec::new());
+        for attempt in 0..attempts {
+            if attempt > 0 {
+                let (required, optional) = scratch.indices.split_at_mut(required);
+                shuffle(required, &mut next_rand);
+                shuffle(optional, &mut next_rand);
+            }
+            let packed = match self.pack_cloned_with(&mut scratch, into_rect, Some(&groups)) {
+                Ok(packed) => {
+                    self.scratch = scratch;
+                    return Ok(packed);
+                }
+                Err(err) => err.into_packed(),
+            };
+            let area: usize = packed.iter().map(|item| item.rect.area()).sum();
+            if best.map_or(true, |(best_area, _)| area > best_area) {
+                best = Some((area, attempt));
+                best_order.clone_from(&scratch.indices);
+                best_packed = packed;
+            }
+        }
+
+        // leave the best layout behind, like `pack` does, rather than the last one
+        if best.is_some_and(|(_, attempt)| attempt + 1 < attempts) {
+            scratch.indices = best_order;
+            let _ = self.pack_cloned_with(&mut scratch, into_rect, Some(&groups));
+        }
+        self.scratch = scratch;
+        Err(best_packed)
+    }
+
+    /// Like [`pack`](Packer::pack), but returns a [`PackOutcome`], which holds both the
+    /// packed items and the ones that weren't packed.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect};
+    /// let mut packer = Packer::with_items((0..3).map(|i| Item::no_rotation(i, 8, 8)));
+    ///
+    /// let outcome = packer.pack_outcome(Rect::of_size(16, 16));
+    /// assert!(outcome.is_complete());
+    /// assert_eq!(outcome.packed().len(), 3);
+    /// assert!(outcome.unpacked().is_empty());
+    ///
+    /// let outcome = packer.pack_outcome(Rect::of_size(16, 8));
+    /// assert!(!outcome.is_complete());
+    /// assert_eq!(outcome.packed().len(), 2);
+    /// assert_eq!(outcome.unpacked().len(), 1);
+    ///
+    /// // items left out without failing the pack still leave it incomplete, whether
+    /// // they're optional or skipped for being too big
+    /// let mut optional = Item::no_rotation(3, 32, 32);
+    /// optional.optional = true;
+    /// packer.push(optional);
+    /// let outcome = packer.pack_outcome(Rect::of_size(16, 16));
+    /// assert!(!outcome.is_complete());
+    /// assert_eq!(outcome.unpacked().len(), 1);
+    ///
+    /// let items = (0..4).map(|i| Item::no_rotation(i, 8 << i, 8));
+    /// let mut packer = Packer::with_items(items).with_skip_oversized(true);
+    /// let outcome = packer.pack_outcome(Rect::of_size(16, 16));
+    /// assert!(!outcome.is_complete());
+    /// assert_eq!(outcome.unpacked().len(), 2);
+    /// ```
+    pub fn pack_outcome(&mut self, into_rect: Rect) -> PackOutcome<'_, T> {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let mut packed = Vec::with_capacity(self.items_to_pack.len());
+        let mut was_packed = vec![false; self.items_to_pack.len()];
+        let stopped = self.pack_with(&mut scratch, into_rect, false, |item_i, rect| {
+            was_packed[item_i] = true;
+            packed.push(Self::packed_item(&self.items_to_pack[item_i], rect))
+        });
+        self.scratch = scratch;
+
+        let unpacked: Vec<_> = self
+            .items_to_pack
+            .iter()
+            .zip(was_packed)
+            .filter(|(_, was_packed)| !was_packed)
+            .map(|(item, _)| item.as_ref())
+            .collect();
+        PackOutcome {
+            packed,
+            complete: stopped.is_ok() && unpacked.is_empty(),
+            unpacked,
+        }
+    }
+
+    /// Like [`pack`](Packer::pack), but also returns the index of each packed item in
+    /// the order the items were added to the packer, since `pack` packs them (and
+    /// returns them) in a different order.
+    ///
+    /// On failure, the items that were packed (and their indices) are returned.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// // added smallest first, so they are packed in reverse order
+    /// let items = vec![
+    ///     Item::new('a', 2, 2, Rotation::None),
+    ///     Item::new('b', 4, 2, Rotation::None),
+    ///     Item::new('c', 4, 4, Rotation::None),
+    /// ];
+    /// let mut packer = Packer::with_items(items.clone());
+    /// let indexed = packer.pack_indexed(Rect::of_size(4, 8)).unwrap();
+    /// assert_eq!(indexed.indices, [2, 1, 0]);
+    ///
+    /// // the indices map each packed item back to the one it came from
+    /// for (packed, &i) in indexed.packed.items.iter().zip(&indexed.indices) {
+    ///     assert_eq!(packed.data, items[i].data);
+    /// }
+    /// ```
+    pub fn pack_indexed(&mut self, into_rect: Rect) -> Result<IndexedPack<T>, IndexedPack<T>> {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let mut items = Vec::with_capacity(self.items_to_pack.len());
+        let mut indices = Vec::with_capacity(self.items_to_pack.len());
+        let stopped = self.pack_with(&mut scratch, into_rect, false, |item_i, rect| {
+            items.push(Self::packed_item(&self.items_to_pack[item_i], rect));
+            indices.push(item_i);
+        });
+        self.scratch = scratch;
+
+        let indexed = IndexedPack {
+            packed: PackedItems {
+                w: into_rect.w,
+                h: into_rect.h,
+                items,
+            },
+            indices,
+        };
+        match stopped {
+            Ok(()) => Ok(indexed),
+            Err(_) => Err(indexed),
+        }
+    }
+
+    /// Like [`pack`](Packer::pack), but also returns a [`PackStep`] for every item
+    /// packed, in the order they were placed, for stepping through how the items were
+    /// packed (such as to animate it).
+    ///
+    /// Each step has the free space nodes split off to make room for its item. Items
+    /// that are placed together (the members of a [group](Item::group), or items that
+    /// [share a slot](Packer::with_dedup_by)) only record the nodes on the first of
+    /// them. With [`Strategy::Shelf`] there is no tree of free space, so no nodes are
+    /// recorded. Finding the new nodes means comparing every node before and after
+    /// each item is placed, so this is much slower than `pack`.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let items = [Item::new(0, 8, 8, Rotation::None), Item::new(1, 8, 4, Rotation::None)];
+    /// let mut packer = Packer::with_items(items);
+    /// let (result, steps) = packer.pack_traced(Rect::of_size(16, 16));
+    /// let packed = result.unwrap();
+    ///
+    /// // one step for each item placed, in the same order
+    /// assert_eq!(steps.len(), packed.len());
+    /// for (step, packed) in steps.iter().zip(&packed) {
+    ///     assert_eq!((step.index, step.rect), (packed.data, packed.rect));
+    /// }
+    ///
+    /// // the first item is placed at the top-left of the empty container, splitting
+    /// // off the space to its right and below it
+    /// assert_eq!(steps[0].rect, Rect::new(0, 0, 8, 8));
+    /// assert_eq!(steps[0].created, [Rect::new(8, 0, 8, 16), Rect::new(0, 8, 16, 8)]);
+    ///
+    /// // the second goes into the top of the space to the right, leaving what's below
+    /// assert_eq!(steps[1].rect, Rect::new(8, 0, 8, 4));
+    /// assert_eq!(steps[1].created, [Rect::new(8, 4, 8, 12)]);
+    ///
+    /// // and everything is placed exactly where `pack` places it
+    /// let again = packer.pack(Rect::of_size(16, 16)).unwrap();
+    /// assert!(again.iter().zip(&packed).all(|(a, b)| a.rect == b.rect));
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn pack_traced(
+        &mut self,
+        into_rect: Rect,
+    ) -> (
+        Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>>,
+        Vec<PackStep>,
+    ) {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let groups = self.sort_order(&mut scratch);
+        self.start_container(&mut scratch, into_rect);
+
+        let mut packed = Vec::with_capacity(self.items_to_pack.len());
+        let mut steps = Vec::with_capacity(self.items_to_pack.len());
+        let mut leaves = Vec::new();
+        let key = |rect: &Rect| (rect.x, rect.y, rect.w, rect.h);
+        let mut complete = true;
+        for ind in 0..scratch.indices.len() {
+            leaves.clear();
+            leaves.extend(scratch.tree.leaf_rects().map(key));
+            leaves.sort_unstable();
+
+            let first = steps.len();
+            let mut on_packed = |item_i: usize, rect| {
+                packed.push(Self::packed_item(&self.items_to_pack[item_i], rect));
+                steps.push(PackStep {
+                    index: item_i,
+                    rect,
+                    created: Vec::new(),
+                });
+            };
+            let stopped = self.pack_next(&mut scratch, &groups, ind, false, &mut on_packed);
+
+            // whatever leaves weren't there before were split off by this step
+            if let Some(step) = steps.get_mut(first) {
+                let created = scratch.tree.leaf_rects().copied();
+                let created = created.filter(|rect| leaves.binary_search(&key(rect)).is_err());
+                let into_rect = &scratch.packed_into;
+                step.created = created
+                    .map(|rect| from_corner(self.origin, into_rect, rect))
+                    .collect();
+            }
+            if stopped.is_err() {
+                complete = false;
+                break;
+            }
+        }
+        self.scratch = scratch;
+        match complete {
+            true => (Ok(packed), steps),
+            false => (Err(packed), steps),
+        }
+    }
+
+    //the packed result for `item`, packed into `rect`
+    #[inline]
+    fn packed_item(item: &Item<T>, rect: Rect) -> PackedItem<T> {
+        PackedItem {
+            data: item.data.clone(),
+            rect,
+            rotated: rect.w != item.packed_size().0,
+            scale: item.scale,
+            optional: item.optional,
+            user_rotation: item.user_rotation,
+        }
+    }
+
+    /// Packs as many items as possible into the `first` container, and then the rest
+    /// into the `second`, like two pages of an atlas.
+    ///
+    /// Unlike [`pack`](Packer::pack), packing the first container doesn't stop when an
+    /// item doesn't fit, so smaller items after it can still fill the gaps. If some
+    /// items don't fit into either container (or are left out because the
+    /// [fragmentation limit](Packer::with_max_fragmentation) stopped packing), returns
+    /// what was packed as an error, and the items left out can be found with
+    /// [`unpacked`](Packer::unpacked).
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect};
+    /// let items = (0..8).map(|i| Item::no_rotation(i, 8, 8));
+    /// let mut packer = Packer::with_items(items);
+    /// let (first, second) = packer
+    ///     .pack_overflow(Rect::of_size(16, 16), Rect::of_size(16, 16))
+    ///     .unwrap();
+    /// assert_eq!((first.items.len(), second.items.len()), (4, 4));
+    /// assert_eq!(first.occupancy(), 1.0);
+    /// assert_eq!(second.occupancy(), 1.0);
+    ///
+    /// // a ninth item doesn't fit into either
+    /// packer.push(Item::no_rotation(8, 8, 8));
+    /// assert!(packer.pack_overflow(Rect::of_size(16, 16), Rect::of_size(16, 16)).is_err());
+    /// assert_eq!(packer.unpacked().count(), 1);
+    ///
+    /// // packing stops once the free space splits up too much, but the items left out
+    /// // of the first container still go into the second (where it stops again)
+    /// let items = (0..3).map(|i| Item::no_rotation(i, 8, 8));
+    /// let mut packer = Packer::with_items(items).with_max_fragmentation(0.5);
+    /// let (first, second) = packer
+    ///     .pack_overflow(Rect::of_size(16, 16), Rect::of_size(16, 16))
+    ///     .unwrap_err();
+    /// assert_eq!((first.items.len(), second.items.len()), (1, 1));
+    /// assert_eq!(packer.unpacked().map(|item| item.data).collect::<Vec<_>>(), [2]);
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn pack_overflow(
+        &mut self,
+        first: Rect,
+        second: Rect,
+    ) -> Result<(PackedItems<T>, PackedItems<T>), (PackedItems<T>, PackedItems<T>)> {
+        // pack everything that fits into the first container
+        // (tracking what got placed, since a pass can stop before visiting everything)
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let mut first_items = Vec::with_capacity(self.items_to_pack.len());
+        let mut placed = vec![false; self.items_to_pack.len()];
+        let _ = self.pack_with(&mut scratch, first, true, |item_i, rect| {
+            placed[item_i] = true;
+            first_items.push(Self::packed_item(&self.items_to_pack[item_i], rect))
+        });
+        let overflow: Vec<usize> = (0..placed.len()).filter(|&i| !placed[i]).collect();
+
+        // then pack the rest on their own into the second one
+        let mut items: Vec<Option<Item<T>>> = self.items_to_pack.drain(..).map(Some).collect();
+        self.items_to_pack
+            .extend(overflow.iter().map(|&i| items[i].take().unwrap()));
+        let mut second_items = Vec::with_capacity(self.items_to_pack.len());
+        let mut placed = vec![false; overflow.len()];
+        let _ = self.pack_with(&mut scratch, second, true, |item_i, rect| {
+            placed[item_i] = true;
+            second_items.push(Self::packed_item(&self.items_to_pack[item_i], rect))
+        });
+        scratch.unpacked.clear();
+        scratch.unpacked.extend(
+            (0..placed.len())
+                .filter(|&i| !placed[i])
+                .map(|i| overflow[i]),
+        );
+        let complete = scratch.unpacked.is_empty();
+
+        // and put the items back where they were
+        for (&i, item) in overflow.iter().zip(self.items_to_pack.drain(..)) {
+            items[i] = Some(item);
+        }
+        self.items_to_pack
+            .extend(items.into_iter().map(Option::unwrap));
+        self.scratch = scratch;
+
+        let first = PackedItems {
+            w: first.w,
+            h: first.h,
+            items: first_items,
+        };
+        let second = PackedItems {
+            w: second.w,
+            h: second.h,
+            items: second_items,
+        };
+        match complete {
+            true => Ok((first, second)),
+            false => Err((first, second)),
+        }
+    }
+
+    /// Spreads the items across exactly `bins` containers the size of `bin`, so that
+    /// each is filled with about the same area, and packs each of them.
+    ///
+    /// Items are dealt out largest first, each to whichever container has the least
+    /// area so far (a [group](Item::group) is dealt out as a whole), so no container
+    /// ends up with more than one item's worth of area more than another. If the items
+    /// dealt to any container don't all fit into it, returns what was packed into
+    /// each as an error, and the items left out of every container can be found with
+    /// [`unpacked`](Packer::unpacked).
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let items = [
+    ///     Item::new('A', 8, 8, Rotation::None),
+    ///     Item::new('B', 8, 8, Rotation::None),
+    ///     Item::new('C', 4, 8, Rotation::None),
+    ///     Item::new('D', 4, 8, Rotation::None),
+    ///     Item::new('E', 4, 4, Rotation::None),
+    ///     Item::new('F', 4, 4, Rotation::None),
+    /// ];
+    /// let mut packer = Packer::with_items(items);
+    ///
+    /// // each page gets one of each size, so they're filled the same
+    /// let pages = packer.pack_balanced(2, Rect::of_size(16, 8)).unwrap();
+    /// let contents: Vec<Vec<char>> = pages
+    ///     .iter()
+    ///     .map(|page| page.items.iter().map(|item| item.data).collect())
+    ///     .collect();
+    /// assert_eq!(contents, [['A', 'C', 'E'], ['B', 'D', 'F']]);
+    /// assert!(pages.iter().all(|page| page.rects().map(|rect| rect.area()).sum::<usize>() == 112));
+    ///
+    /// // but they don't fit into three smaller pages: one gets both medium items, so the
+    /// // small ones are dealt to the pages already filled by a large one
+    /// let pages = packer.pack_balanced(3, Rect::of_size(8, 8)).unwrap_err();
+    /// let packed: usize = pages.iter().map(|page| page.items.len()).sum();
+    /// assert_eq!(packed, 4);
+    /// assert_eq!(packer.unpacked().map(|item| item.data).collect::<Vec<_>>(), ['E', 'F']);
+    /// ```
+    pub fn pack_balanced(
+        &mut self,
+        bins: usize,
+        bin: Rect,
+    ) -> Result<Vec<PackedItems<T>>, Vec<PackedItems<T>>> {
+        if bins == 0 {
+            return match self.is_empty() {
+                true => Ok(Vec::new()),
+                false => Err(Vec::new()),
+            };
+        }
+
+        // the items to deal out, with the members of each group kept together
+        let mut units: Vec<(usize, Vec<usize>)> = Vec::new();
+        let mut group_units: HashMap<u32, usize> = HashMap::new();
+        for (i, item) in self.items_to_pack.iter().enumerate() {
+            let (w, h) = item.packed_size();
+            let unit = match item.group {
+                Some(id) => *group_units.entry(id).or_insert_with(|| {
+                    units.push((0, Vec::new()));
+                    units.len() - 1
+                }),
+                None => {
+                    units.push((0, Vec::new()));
+                    units.len() - 1
+                }
+            };
+            units[unit].0 = units[unit].0.saturating_add(w.saturating_mul(h));
+            units[unit].1.push(i);
+        }
+
+        // deal the largest out first, each to the container with the least area so far
+        units.sort_by_key(|&(area, _)| Reverse(area));
+        let mut dealt = vec![(0usize, Vec::new()); bins];
+        for (area, members) in units {
+            let (bin_area, bin_items) = dealt.iter_mut().min_by_key(|(area, _)| *area).unwrap();
+            *bin_area = bin_area.saturating_add(area);
+            bin_items.extend(members);
+        }
+
+        // pack each container's items on their own
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let mut items: Vec<Option<Item<T>>> = self.items_to_pack.drain(..).map(Some).collect();
+        let mut pages = Vec::with_capacity(bins);
+        let mut complete = true;
+        let mut unpacked = Vec::new();
+        for (_, mut indices) in dealt {
+            indices.sort_unstable();
+            self.items_to_pack
+                .extend(indices.iter().map(|&i| items[i].take().unwrap()));
+            let mut packed = Vec::with_capacity(self.items_to_pack.len());
+            let mut placed = vec![false; indices.len()];
+            complete &= self
+                .pack_with(&mut scratch, bin, false, |item_i, rect| {
+                    placed[item_i] = true;
+                    packed.push(Self::packed_item(&self.items_to_pack[item_i], rect))
+                })
+                .is_ok();
+            unpacked.extend(
+                (0..placed.len())
+                    .filter(|&i| !placed[i])
+                    .map(|i| indices[i]),
+            );
+            for (&i, item) in indices.iter().zip(self.items_to_pack.drain(..)) {
+                items[i] = Some(item);
+            }
+            pages.push(PackedItems {
+                w: bin.w,
+                h: bin.h,
+                items: packed,
+            });
+        }
+        self.items_to_pack
+            .extend(items.into_iter().map(Option::unwrap));
+        unpacked.sort_unstable();
+        scratch.unpacked = unpacked;
+        self.scratch = scratch;
+
+        match complete {
+            true => Ok(pages),
+            false => Err(pages),
+        }
+    }
+
+    /// Update the layout of the last [`pack`](Packer::pack) in place, without packing
+    /// everything again: the `removed` rects are freed up, and then the `added` items
+    /// are packed into the free space around the items that are still there.
+    ///
+    /// Returns the packed `added` items on success, or the ones that were packed before
+    /// one didn't fit. Either way, the packed items become part of the layout, so this
+    /// can be called repeatedly as items come and go. Rects in `removed` that aren't
+    /// part of the layout are ignored. The queued items are not changed. The added items
+    /// are kept apart by the [inter-group spacing](Packer::with_inter_group_spacing),
+    /// just like packed ones.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let items = (0..4).map(|i| Item::new(i, 8, 8, Rotation::None));
+    /// let mut packer = Packer::with_items(items);
+    /// let packed = packer.pack(Rect::of_size(16, 16)).unwrap();
+    ///
+    /// // the container is full, so remove two items to make room for a larger one
+    /// let removed = [packed[0].rect, packed[2].rect];
+    /// let data = 4;
+    /// let added = packer.repack_delta(&[Item::new(&data, 8, 16, Rotation::Allowed)], &removed).unwrap();
+    ///
+    /// let mut layout: Vec<Rect> = vec![packed[1].rect, packed[3].rect];
+    /// layout.extend(added.iter().map(|i| i.rect));
+    /// for (i, a) in layout.iter().enumerate() {
+    ///     assert!(Rect::of_size(16, 16).contains(a));
+    ///     for b in &layout[i + 1..] {
+    ///         assert!(!a.overlaps(b));
+    ///     }
+    /// }
+    ///
+    /// // with spacing, a wider item would crowd its neighbour, so only a snug one fits
+    /// let items = (0..4).map(|i| Item::new(i, 8, 8, Rotation::None));
+    /// let mut packer = Packer::with_items(items).with_inter_group_spacing(2);
+    /// let packed = packer.pack(Rect::of_size(18, 18)).unwrap();
+    /// let wide = Item::new(&data, 9, 8, Rotation::None);
+    /// assert!(packer.repack_delta(&[wide], &[packed[0].rect]).is_err());
+    /// let snug = Item::new(&data, 8, 8, Rotation::None);
+    /// assert_eq!(packer.repack_delta(&[snug], &[]).unwrap()[0].rect, packed[0].rect);
+    /// ```
+    pub fn repack_delta(
+        &mut self,
+        added: &[Item<&T>],
+        removed: &[Rect],
+    ) -> Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>> {
+        // rebuild the free space from the rects still in place
+        // (the removed rects are measured from the origin corner, but placed ones aren't)
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let into_rect = scratch.packed_into;
+        let removed: Vec<Rect> = removed
+            .iter()
+            .map(|&rect| from_corner(self.origin, &into_rect, rect))
+            .collect();
+        let (placed_area, rotated) = (&mut scratch.placed_area, &mut scratch.rotated);
+        scratch
+            .placed
+            .retain(|(rect, rotations)| match removed.contains(rect) {
+                true => {
+                    *placed_area = placed_area.saturating_sub(rect.area());
+                    *rotated -= rotations;
+                    false
+                }
+                false => true,
+            });
+        // (padded the same way pack() pads them, to keep the spacing between them)
+        let pad = self.inter_group_spacing;
+        let capacity = (scratch.placed.len() + added.len()) * 2;
+        scratch.tree.reset(
+            padded(&into_rect, pad),
+            capacity,
+            self.use_index,
+            self.heuristic,
+        );
+        for (rect, _) in &scratch.placed {
+            scratch.tree.split_tree(&padded(rect, pad), 0);
+        }
+        self.apply_mask(&mut scratch);
+        scratch.tree.compact();
+
+        // pack the largest added items first, like pack() does
+        let mut order: Vec<usize> = (0..added.len()).collect();
+        order.sort_by(|&a, &b| added[b].sort_priority().cmp(&added[a].sort_priority()));
+
+        let mut packed = Vec::with_capacity(added.len());
+        let mut complete = true;
+        for i in order {
+            match self.place_added(&mut scratch, &added[i]) {
+                Some(item) => packed.push(item),
+                None => {
+                    complete = false;
+                    break;
+                }
+            }
+        }
+        self.scratch = scratch;
+
+        match complete {
+            true => Ok(packed),
+            false => Err(packed),
+        }
+    }
+
+    /// Packs `items` into `into_rect` one at a time, in the order they come, until one
+    /// doesn't fit. Returns the items that were packed, and the rest of `items`,
+    /// starting with the one that didn't fit.
+    ///
+    /// Since items are only taken from `items` as they're packed, it can be lazy, or
+    /// even endless. The packed items become the layout used by
+    /// [`repack_delta`](Packer::repack_delta), so more can be added to it afterwards.
+    /// Like `repack_delta`, this always uses [`Strategy::Tree`], and the queued items
+    /// are not changed.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let ids: Vec<usize> = (0..100).collect();
+    /// let items = ids.iter().map(|id| Item::new(id, 4, 4, Rotation::None));
+    ///
+    /// // only 8 of the items fit
+    /// let mut packer = Packer::new();
+    /// let (packed, mut rest) = packer.fill(Rect::of_size(16, 8), items);
+    /// assert_eq!(packed.len(), 8);
+    /// assert!(packed.iter().map(|item| item.data).eq(0..8));
+    ///
+    /// // and the rest start with the first that didn't
+    /// assert_eq!(rest.next().map(|item| *item.data), Some(8));
+    /// assert_eq!(rest.count(), 91);
+    /// ```
+    pub fn fill<'a, I>(
+        &mut self,
+        into_rect: Rect,
+        items: I,
+    ) -> (Vec<PackedItem<T>>, Peekable<I::IntoIter>)
+    where
+        T: 'a,
+        I: IntoIterator<Item = Item<&'a T>>,
+    {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let pad = self.inter_group_spacing;
+        scratch
+            .tree
+            .reset(padded(&into_rect, pad), 0, self.use_index, self.heuristic);
+        scratch.packed_into = into_rect;
+        scratch.placed.clear();
+        scratch.unpacked.clear();
+        scratch.rotated = 0;
+        scratch.placed_area = 0;
+
+        let mut items = items.into_iter().peekable();
+        let mut packed = Vec::new();
+        while let Some(item) = items.peek() {
+            match self.place_added(&mut scratch, item) {
+                Some(item) => packed.push(item),
+                None => break,
+            }
+            items.next();
+        }
+        self.scratch = scratch;
+
+        (packed, items)
+    }
+
+    //pack `item` into the free space left in the layout in `scratch`, and add it to it
+    fn place_added(&self, scratch: &mut Scratch, item: &Item<&T>) -> Option<PackedItem<T>> {
+        // pad the space reserved for the item like place_item() does, to keep the spacing
+        let (rot, pad) = (self.rotation_of(item), self.inter_group_spacing);
+        let (w, h) = item.packed_size();
+        let (padded_w, padded_h) = (
+            quantized(w, self.size_quantum).saturating_add(pad),
+            quantized(h, self.size_quantum).saturating_add(pad),
+        );
+        let rotate = rot != Rotation::None && padded_w != padded_h;
+        let policy = &self.rotation_policy;
+        let allow = |rect: &Rect| allows_rotation(policy, item, rect);
+        let region = item.allowed.map(|region| padded(&region, pad));
+        let tiebreak = self.node_tiebreak.as_deref();
+        let rect = match scratch.tree.place(
+            padded_w,
+            padded_h,
+            rotate,
+            region.as_ref(),
+            tiebreak,
+            allow,
+        )? {
+            rect if rect.w == padded_w => Rect::new(rect.x, rect.y, w, h),
+            rect => Rect::new(rect.x, rect.y, h, w),
+        };
+        debug_assert_inside(&scratch.packed_into, &rect);
+        scratch.placed.push((rect, (rect.w != w) as usize));
+        scratch.rotated += (rect.w != w) as usize;
+        scratch.placed_area = scratch.placed_area.saturating_add(rect.area());
+        Some(PackedItem {
+            data: item.data.clone(),
+            rect: from_corner(self.origin, &scratch.packed_into, rect),
+            rotated: rect.w != w,
+            scale: item.scale,
+            optional: item.optional,
+            user_rotation: item.user_rotation,
+        })
+    }
+
+    /// Packs the items into `into_rect` with each of the `heuristics`, and returns
+    /// whichever heuristic covered the most area, along with what it packed.
+    ///
+    /// If several heuristics cover the same area (because they all packed every item,
+    /// for example), the one whose packed items span the smallest bounds wins, and
+    /// otherwise the first. This runs [`pack`](Packer::pack) once for each heuristic,
+    /// and the winner's layout is kept for [`repack_delta`](Packer::repack_delta). The
+    /// packer's own heuristic isn't changed. If `heuristics` is empty, it is used.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect, Heuristic};
+    /// let sizes = [(3, 5), (3, 6), (4, 5), (5, 4), (6, 5), (5, 5), (5, 1), (3, 1), (3, 6), (2, 1), (1, 5), (2, 6)];
+    /// let items = sizes.iter().map(|&(w, h)| Item::no_rotation((), w, h));
+    /// let mut packer = Packer::with_items(items);
+    ///
+    /// let heuristics = [Heuristic::BestAreaFit, Heuristic::TopLeft];
+    /// let (heuristic, packed) = packer.pack_best_heuristic(Rect::of_size(10, 10), &heuristics);
+    /// assert_eq!(heuristic, Heuristic::TopLeft);
+    ///
+    /// let area = |packed: &[crunch::PackedItem<()>]| packed.iter().map(|i| i.rect.area()).sum::<usize>();
+    /// let default_packed = packer.pack(Rect::of_size(10, 10)).unwrap_err();
+    /// assert!(area(&packed.unwrap_err()) > area(&default_packed));
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn pack_best_heuristic(
+        &mut self,
+        into_rect: Rect,
+        heuristics: &[Heuristic],
+    ) -> (Heuristic, Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>>) {
+        let original = self.heuristic;
+        let heuristics = match heuristics {
+            [] => std::slice::from_ref(&original),
+            heuristics => heuristics,
+        };
+
+        // most area covered, then smallest bounds
+        let rank = |packed: &[PackedItem<T>]| {
+            let area: usize = packed.iter().map(|i| i.rect.area()).sum();
+            let right = packed.iter().map(|i| i.rect.right()).max().unwrap_or(0);
+            let bottom = packed.iter().map(|i| i.rect.bottom()).max().unwrap_or(0);
+            (area, Reverse(right * bottom))
+        };
+
+        let mut best = None;
+        for &heuristic in heuristics {
+            self.heuristic = heuristic;
+            let mut scratch = Scratch::new();
+            let result = self
+                .pack_cloned_with(&mut scratch, into_rect, None)
+                .map_err(PackError::into_packed);
+            let score = match &result {
+                Ok(packed) | Err(packed) => rank(packed),
+            };
+            if best.as_ref().map_or(true, |(_, _, _, best)| score > *best) {
+                best = Some((heuristic, result, scratch, score));
+            }
+        }
+        self.heuristic = original;
+
+        let (heuristic, result, scratch, _) = best.unwrap();
+        self.scratch = scratch;
+        (heuristic, result)
+    }
+
+    /// Attempts to pack the items into a `w` x `h` container, or a `h` x `w` one if
+    /// they don't fit into that.
+    ///
+    /// On success, returns the packed items, and `true` if the container had to be
+    /// rotated to fit them. If neither orientation fits, returns the items packed into
+    /// whichever orientation covered more area before failing.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation};
+    /// // a landscape item only fits onto a landscape sheet
+    /// let mut packer = Packer::with_items([Item::new('A', 10, 2, Rotation::None)]);
+    /// let (packed, rotated) = packer.pack_best_orientation(2, 10).unwrap();
+    /// assert!(rotated);
+    /// assert_eq!((packed.w, packed.h), (10, 2));
+    /// ```
+    pub fn pack_best_orientation(
+        &mut self,
+        w: usize,
+        h: usize,
+    ) -> Result<(PackedItems<T>, bool), Vec<PackedItem<T>>> {
+        let portrait = match self.pack(Rect::of_size(w, h)) {
+            Ok(items) => return Ok((PackedItems { w, h, items }, false)),
+            Err(items) => items,
+        };
+        let landscape = match self.pack(Rect::of_size(h, w)) {
+            Ok(items) => return Ok((PackedItems { w: h, h: w, items }, true)),
+            Err(items) => items,
+        };
+
+        let area = |items: &[PackedItem<T>]| items.iter().map(|i| i.rect.area()).sum::<usize>();
+        match area(&landscape) > area(&portrait) {
+            true => Err(landscape),
+            false => Err(portrait),
+        }
+    }
+
+    /// Attempts to pack the items into a container exactly `width` wide, and as short
+    /// as possible while not exceeding `max_height`.
+    ///
+    /// The height is found with a binary search, so this calls [`pack`](Packer::pack)
+    /// roughly `log2(max_height)` times. On success, the returned container's height
+    /// is the bottom edge of the lowest packed item.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation};
+    /// let mut packer = Packer::with_items((0..8).map(|i| Item::new(i, 16, 16, Rotation::None)));
+    /// let packed = packer.pack_fixed_width(64, 1024).unwrap();
+    /// assert_eq!((packed.w, packed.h), (64, 32));
+    ///
+    /// assert!(packer.pack_fixed_width(64, 16).is_err());
+    ///
+    /// // the rotation override is respected, so these can lie down in a single row
+    /// let items = (0..4).map(|i| Item::new(i, 4, 16, Rotation::None));
+    /// let mut packer = Packer::with_items(items).with_rotation_override(Some(Rotation::Allowed));
+    /// let packed = packer.pack_fixed_width(64, 1024).unwrap();
+    /// assert_eq!((packed.w, packed.h), (64, 4));
+    /// ```
+    #[allow(clippy::result_unit_err)]
+    pub fn pack_fixed_width(
+        &mut self,
+        width: usize,
+        max_height: usize,
+    ) -> Result<PackedItems<T>, ()> {
+        // no item can be shorter than its shortest side, or its height if it can't rotate
+        let tallest = self
+            .items_to_pack
+            .iter()
+            .map(|i| (self.rotation_of(i) != Rotation::None, i.packed_size()))
+            .map(|(rotate, (w, h))| match rotate && h <= width {
+                true => w.min(h),
+                false => h,
+            })
+            .max()
+            .unwrap_or(0);
+        let mut lo = tallest.max(self.total_area().div_ceil(width.max(1)));
+        let mut hi = max_height;
+
+        let mut best = None;
+        while lo <= hi {
+            let mid = lo + (hi - lo) / 2;
+            match self.pack(Rect::of_size(width, mid)) {
+                Ok(items) => {
+                    best = Some(items);
+                    match mid {
+                        0 => break,
+                        _ => hi = mid - 1,
+                    }
+                }
+                Err(_) => lo = mid + 1,
+            }
+        }
+
+        best.map(|items| PackedItems {
+            w: width,
+            h: items.iter().map(|i| i.rect.bottom()).max().unwrap_or(0),
+            items,
+        })
+        .ok_or(())
+    }
+
+    /// Lays the items out in rows of `cols` items each, left-to-right and then
+    /// top-to-bottom in the order they were added, with each row as tall as its
+    /// tallest item. Items are never rotated.
+    ///
+    /// This doesn't search for the best fit at all, which gives the predictable
+    /// layout expected for things like animation strips. If a row is wider than
+    /// `into_rect`, or the rows are taller than it, this fails with the items that
+    /// were packed before that. The layout isn't kept for
+    /// [`repack_delta`](Packer::repack_delta).
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rect};
+    /// let mut packer = Packer::with_items((0..7).map(|i| Item::no_rotation(i, 8, 4 + i)));
+    /// let packed = packer.pack_rows(3, Rect::of_size(32, 32)).unwrap();
+    ///
+    /// let rows: Vec<_> = packed.iter().map(|p| (p.rect.x, p.rect.y)).collect();
+    /// assert_eq!(rows, [
+    ///     (0, 0), (8, 0), (16, 0),
+    ///     (0, 6), (8, 6), (16, 6),
+    ///     (0, 15),
+    /// ]);
+    ///
+    /// // 4 frames of 8 pixels don't fit across 24 pixels
+    /// assert_eq!(packer.pack_rows(4, Rect::of_size(24, 64)).unwrap_err().len(), 3);
+    /// ```
+    pub fn pack_rows(
+        &mut self,
+        cols: usize,
+        into_rect: Rect,
+    ) -> Result<Vec<PackedItem<T>>, Vec<PackedItem<T>>> {
+        let mut packed = Vec::with_capacity(self.items_to_pack.len());
+        let mut y = into_rect.y;
+        for row in self.items_to_pack.chunks(cols.max(1)) {
+            let row_h = row.iter().map(|i| i.packed_size().1).max().unwrap_or(0);
+            let mut x = into_rect.x;
+            for item in row {
+                let (w, h) = item.packed_size();
+                let rect = Rect::new(x, y, w, h);
+                if rect.right() > into_rect.right() || y + row_h > into_rect.bottom() {
+                    return Err(packed);
+                }
+                packed.push(Self::packed_item(item, rect));
+                x += w;
+            }
+            y += row_h;
+        }
+        Ok(packed)
+    }
+
+    /// Attempts to pack the supplied items into the smallest power of 2 container
+    /// it possibly can while not exceeding the provided `max_size`.
+    ///
+    /// On success, returns the size of the container (a power of 2) and the packed items.
+    /// The container itself, as a `Rect`, is [`PackedItems::bounds`]. Of the containers
+    /// the items fit into, the one with the smallest area is picked, and of those with
+    /// the same area, the one with the shortest longest side (so square containers are
+    /// preferred over wide or tall ones).
+    ///
+    /// If `max_size` isn't a power of 2, it is rounded down to the nearest one, since
+    /// no larger power of 2 container would fit within it anyway.
+    ///
+    /// If there are no items to pack, this succeeds with an empty `0 x 0` container
+    /// rather than the smallest power of 2, since no space is needed at all. If the
+    /// items' combined area is too large for a `usize`, no container could fit
+    /// them, so this fails without trying any.
+    ///
+    /// The items are only sorted into the order to pack them in once, and that order
+    /// is reused for every container tried, so the result is exactly what
+    /// [`pack`](Packer::pack)ing into the same size container would give.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, Rect};
+    /// let items = [
+    ///     Item::new('A', 8, 8, Rotation::Allowed),
+    ///     Item::new('B', 4, 8, Rotation::Allowed),
+    ///     Item::new('C', 4, 4, Rotation::Allowed),
+    /// ];
+    /// let mut packer = Packer::with_items(items);
+    ///
+    /// // the 112 pixels of items need at least 128, and the wide container is tried first
+    /// let packed = packer.pack_into_po2(1024).unwrap();
+    /// assert_eq!((packed.w, packed.h), (16, 8));
+    /// let direct = packer.pack(packed.bounds()).unwrap();
+    /// assert!(packed.items.iter().zip(&direct).all(|(a, b)| (a.data, a.rect) == (b.data, b.rect)));
+    /// ```
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation};
+    /// let mut packer = Packer::with_items([Item::new('A', 600, 600, Rotation::None)]);
+    /// let packed = packer.pack_into_po2(1024).unwrap();
+    /// assert_eq!((packed.w, packed.h), (1024, 1024));
+    ///
+    /// // 1000 is rounded down to 512, which is too small
+    /// assert!(packer.pack_into_po2(1000).is_err());
+    ///
+    /// let mut packer = Packer::<char>::new();
+    /// let packed = packer.pack_into_po2(1024).unwrap();
+    /// assert_eq!((packed.w, packed.h), (0, 0));
+    ///
+    /// packer.push(Item::new('A', 3, 3, Rotation::None));
+    /// let packed = packer.pack_into_po2(1024).unwrap();
+    /// assert_eq!((packed.w, packed.h), (4, 4));
+    ///
+    /// // the items' combined area doesn't even fit in a usize
+    /// let huge = usize::MAX / 4;
+    /// packer.extend((0..8).map(|_| Item::new('H', huge, 2, Rotation::None)));
+    /// assert!(packer.pack_into_po2(usize::MAX).is_err());
+    /// ```
+    #[allow(clippy::result_unit_err)]
+    pub fn pack_into_po2(&mut self, max_size: usize) -> Result<PackedItems<T>, ()> {
+        self.pack_into_po2_with_shapes(max_size, &PO2_SHAPES)
+    }
+
+    /// Like [`pack_into_po2`](Packer::pack_into_po2), but only tries square containers,
+    /// for targets (like cube maps) that require them.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation};
+    /// let mut packer = Packer::with_items([Item::new('A', 100, 50, Rotation::None)]);
+    ///
+    /// let packed = packer.pack_into_po2(1024).unwrap();
+    /// assert_eq!((packed.w, packed.h), (128, 64));
+    ///
+    /// let packed = packer.pack_into_square_po2(1024).unwrap();
+    /// assert_eq!((packed.w, packed.h), (128, 128));
+    /// ```
+    #[allow(clippy::result_unit_err)]
+    pub fn pack_into_square_po2(&mut self, max_size: usize) -> Result<PackedItems<T>, ()> {
+        self.pack_into_po2_with_shapes(max_size, &[(1, 1)])
+    }
+
+    /// Like [`pack_into_po2`](Packer::pack_into_po2), but tries the container shapes
+    /// given in `shapes` instead of [`PO2_SHAPES`].
+    ///
+    /// Each shape is a `(w, h)` multiplier applied to every power of 2 size, so `(4, 1)`
+    /// tries containers like `1024 x 256`. Multipliers should be powers of 2 themselves
+    /// if you want the resulting container to be one. Of all the candidate containers,
+    /// the one with the smallest area that fits the items is returned, and if several
+    /// with that area fit, the one with the shortest longest side (the squarest).
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation, PO2_SHAPES};
+    /// let mut packer = Packer::with_items([Item::new('A', 256, 40, Rotation::None)]);
+    ///
+    /// let packed = packer.pack_into_po2(1024).unwrap();
+    /// assert_eq!((packed.w, packed.h), (256, 128));
+    ///
+    /// let shapes = [PO2_SHAPES.as_slice(), &[(4, 1), (1, 4)]].concat();
+    /// let packed = packer.pack_into_po2_with_shapes(1024, &shapes).unwrap();
+    /// assert_eq!((packed.w, packed.h), (256, 64));
+    ///
+    /// // both 32 x 8 and 16 x 16 fit with the same area, so the squarer one is used
+    /// let mut packer = Packer::with_items([Item::new('B', 16, 8, Rotation::None)]);
+    /// let packed = packer.pack_into_po2_with_shapes(1024, &[(1, 1), (4, 1)]).unwrap();
+    /// assert_eq!((packed.w, packed.h), (16, 16));
+    /// ```
+    #[allow(clippy::result_unit_err)]
+    pub fn pack_into_po2_with_shapes(
+        &mut self,
+        max_size: usize,
+        shapes: &[(usize, usize)],
+    ) -> Result<PackedItems<T>, ()> {
+        let candidates = self.po2_candidates(2, max_size, shapes);
+        self.pack_into_first(&candidates)
+    }
+
+    /// Like [`pack_into_po2`](Packer::pack_into_po2), but never tries containers
+    /// smaller than `start_size`, such as when the size the items needed last time
+    /// is already known, so time isn't wasted on containers too small to fit them.
+    ///
+    /// If the items don't fit into a `start_size` container, larger ones are tried
+    /// as usual. If `start_size` isn't a power of 2 no larger than `max_size`, this
+    /// fails without trying any.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation};
+    /// let mut packer = Packer::with_items([Item::new('A', 20, 20, Rotation::None)]);
+    /// let packed = packer.pack_into_po2(1024).unwrap();
+    /// assert_eq!((packed.w, packed.h), (32, 32));
+    ///
+    /// // a 32 x 32 container would still fit, but isn't tried
+    /// let packed = packer.pack_into_po2_from(64, 1024).unwrap();
+    /// assert_eq!((packed.w, packed.h), (64, 64));
+    ///
+    /// // too small a hint just starts the search lower
+    /// let packed = packer.pack_into_po2_from(8, 1024).unwrap();
+    /// assert_eq!((packed.w, packed.h), (32, 32));
+    ///
+    /// assert!(packer.pack_into_po2_from(48, 1024).is_err());
+    /// assert!(packer.pack_into_po2_from(2048, 1024).is_err());
+    /// ```
+    #[allow(clippy::result_unit_err)]
+    pub fn pack_into_po2_from(
+        &mut self,
+        start_size: usize,
+        max_size: usize,
+    ) -> Result<PackedItems<T>, ()> {
+        if !start_size.is_power_of_two() || start_size > max_size {
+            return Err(());
+        }
+        let candidates = self.po2_candidates(start_size, max_size, &PO2_SHAPES);
+        self.pack_into_first(&candidates)
+    }
+
+    //pack into the first of the `candidates` containers the items fit into
+    fn pack_into_first(&mut self, candidates: &[(usize, usize)]) -> Result<PackedItems<T>, ()> {
+        if self.is_empty() {
+            return Ok(PackedItems {
+                w: 0,
+                h: 0,
+                items: Vec::new(),
+            });
+        }
+
+        // the order to pack in is the same for every container, so only sort it once
+        self.prepare();
+        for &(w, h) in candidates {
+            if let Ok(items) = self.pack_prepared(Rect::of_size(w, h)) {
+                return Ok(PackedItems { w, h, items });
+            }
+        }
+
+        Err(())
+    }
+
+    /// Like [`pack_into_po2`](Packer::pack_into_po2), but instead of trying every
+    /// container from the smallest up, this starts from a guess based on the items'
+    /// [`min_bound`](Packer::min_bound), and then bisects towards the smallest
+    /// container that fits. This usually takes far fewer calls to [`pack`](Packer::pack)
+    /// when there are many containers too small to fit the items.
+    ///
+    /// This finds the same container as `pack_into_po2` as long as the items fitting a
+    /// container means they fit every container larger than it in both directions,
+    /// which is almost always the case.
+    ///
+    /// ```
+    /// # use crunch::{Packer, Item, Rotation};
+    /// let items = [
+    ///     Item::new('T', 64, 128, Rotation::Allowed),
+    ///     Item::new('L', 4000, 1, Rotation::None),
+    /// ];
+    /// let mut packer = Packer::with_items(items);
+    ///
+    /// // the long item only fits once the container is 4096 wide
+    /// let packed = packer.pack_into_po2(8192).unwrap();
+    /// assert_eq!((packed.w, packed.h), (4096, 2048));
+    ///
+    /// let bisected = packer.pack_into_po2_bisect(8192).unwrap();
+    /// assert_eq!((bisected.w, bisected.h), (4096, 2048));
+    /// ```
+    #[allow(clippy::result_unit_err)]
+    pub fn pack_into_po2_bisect(&mut self, max_size: usize) -> Result<PackedItems<T>, ()> {
+        if self.is_empty() {
+            return Ok(PackedItems {
+                w: 0,
+                h: 0,
+                items: Vec::new(),
+            });
+        }
+
+        // containers of different shapes don't grow in step (a wide one can fit items
+        // that a larger tall one can't), so each shape is bisected on its own, only
+        // looking at containers smaller than the best fit found so far
+        let candidates = self.po2_candidates(2, max_size, &PO2_SHAPES);
+        let (bound_w, bound_h) = self.min_bound();
+        let bound_area = bound_w.saturating_mul(bound_h);
+        self.prepare();
+        let mut best: Option<(usize, PackedItems<T>)> = None;
+        for &(w_mult, h_mult) in &PO2_SHAPES {
+            let shaped: Vec<usize> = (0..candidates.len())
+                .filter(|&i| best.as_ref().map_or(true, |(best_i, _)| i < *best_i))
+                .filter(|&i| {
+                    let (w, h) = candidates[i];
+                    w.checked_mul(h_mult) == h.checked_mul(w_mult)
+                })
+                .collect();
+
+            // guess the first container at least as big as the minimum bound, then
+            // bisect for the first one that fits
+            let mut guess = shaped
+                .iter()
+                .position(|&i| candidates[i].0.saturating_mul(candidates[i].1) >= bound_area)
+                .unwrap_or(shaped.len().saturating_sub(1));
+            let (mut lo, mut hi) = (0, shaped.len());
+            while lo < hi {
+                let (w, h) = candidates[shaped[guess]];
+                match self.pack_prepared(Rect::of_size(w, h)) {
+                    Ok(items) => {
+                        best = Some((shaped[guess], PackedItems { w, h, items }));
+                        hi = guess;
+                    }
+                    Err(_) => lo = guess + 1,
+                }
+                guess = lo + (hi - lo) / 2;
+            }
+        }
+
+        best.map(|(_, packed)| packed).ok_or(())
+    }
+
+    //every candidate container for packing into a power of 2 from `start_size` up,
+    //smallest area first
+    fn po2_candidates(
+        &self,
+        start_size: usize,
+        max_size: usize,
+        shapes: &[(usize, usize)],
+    ) -> Vec<(usize, usize)> {
+        let min_area = match self.checked_total_area() {
+            Some(area) => area,
+            None => return Vec::new(),
+        };
+        let max_size = match max_size {
+            0 => 0,
+            n => 1 << (usize::BITS - 1 - n.leading_zeros()),
+        };
+
+        // skip the sizes that not even the largest shape could fit the items into
+        // (areas saturate rather than overflow, since a saturated area is already
+        // larger than any the items could need)
+        let area = |w: usize, h: usize| w.saturating_mul(h);
+        let max_shape = shapes.iter().map(|&(w, h)| area(w, h)).max().unwrap_or(1);
+        let min_size = ceil_sqrt(min_area.div_ceil(max_shape.max(1)));
+        let mut size = next_power_of_two_at_least(min_size).max(start_size.max(2));
+
+        // collect every container we could try, and try them smallest area first, and
+        // the squarest first of those with the same area
+        let mut candidates = Vec::new();
+        while size <= max_size {
+            for &(w_mult, h_mult) in shapes {
+                let (w, h) = match (size.checked_mul(w_mult), size.checked_mul(h_mult)) {
+                    (Some(w), Some(h)) => (w, h),
+                    _ => continue,
+                };
+                if w <= max_size
+                    && h <= max_size
+                    && area(w, h) >= min_area
+                    && !candidates.contains(&(w, h))
+                {
+                    candidates.push((w, h));
+                }
+            }
+            size = size.saturating_mul(2);
+        }
+        candidates.sort_by_key(|&(w, h)| (area(w, h), w.max(h)));
+
+        candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //pack with `f`, returning the container it found and how many it tried
+    fn count_calls<F>(packer: &mut Packer<char>, f: F) -> ((usize, usize), usize)
+    where
+        F: FnOnce(&mut Packer<char>) -> Result<PackedItems<char>, ()>,
+    {
+        PACK_PREPARED_CALLS.with(|calls| calls.set(0));
+        let packed = f(packer).unwrap();
+        let calls = PACK_PREPARED_CALLS.with(|calls| calls.get());
+        ((packed.w, packed.h), calls)
+    }
+
+    #[test]
+    fn bisect_tries_fewer_containers() {
+        let mut packer = Packer::with_items([
+            Item::new('T', 64, 128, Rotation::Allowed),
+            Item::new('L', 30000, 1, Rotation::None),
+        ]);
+        let (linear, linear_calls) = count_calls(&mut packer, |p| p.pack_into_po2(32768));
+        let (bisected, bisect_calls) = count_calls(&mut packer, |p| p.pack_into_po2_bisect(32768));
+        assert_eq!(linear, bisected);
+        assert!(bisect_calls * 2 < linear_calls);
     }
 }
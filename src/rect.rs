@@ -1,4 +1,9 @@
 /// A simple rectangle structure used for packing.
+///
+/// Rectangles are half-open: a rect covers the pixels from `x` up to but not
+/// including `x + w` horizontally, and from `y` up to but not including `y + h`
+/// vertically. So two rects that share an edge do not overlap, and a rect's
+/// [`right`](Rect::right) and [`bottom`](Rect::bottom) edges are just outside it.
 #[derive(Debug, Copy, Clone, Default, PartialEq)]
 pub struct Rect {
     pub x: usize,
@@ -22,30 +27,202 @@ impl Rect {
         Self::new(0, 0, w, h)
     }
 
+    /// Create a new `Rect` from a `(w, h)` size tuple, positioned at `(0, 0)`.
+    ///
+    /// ```
+    /// # use crunch::Rect;
+    /// assert_eq!(Rect::from_size_tuple((8, 4)), Rect::of_size(8, 4));
+    /// ```
+    #[inline]
+    pub const fn from_size_tuple((w, h): (usize, usize)) -> Self {
+        Self::of_size(w, h)
+    }
+
     /// The area of the rectangle.
+    ///
+    /// If the area is too large for a `usize`, this saturates at `usize::MAX` instead
+    /// of overflowing.
+    ///
+    /// ```
+    /// # use crunch::Rect;
+    /// assert_eq!(Rect::of_size(3, 4).area(), 12);
+    /// assert_eq!(Rect::of_size(usize::MAX / 2, 4).area(), usize::MAX);
+    /// ```
     #[inline]
     pub const fn area(&self) -> usize {
-        self.w * self.h
+        self.w.saturating_mul(self.h)
+    }
+
+    /// Returns true if the rectangle has a width or height of 0, and so covers no area.
+    ///
+    /// ```
+    /// # use crunch::Rect;
+    /// assert!(Rect::new(5, 5, 0, 10).is_empty());
+    /// assert!(Rect::new(5, 5, 10, 0).is_empty());
+    /// assert!(!Rect::new(5, 5, 1, 1).is_empty());
+    /// ```
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.w == 0 || self.h == 0
     }
 
     /// Returns true if `other` is fully contained inside `self`.
+    ///
+    /// Since [empty](Rect::is_empty) rects cover no area, they never contain
+    /// anything, and are never contained by anything (even themselves).
+    ///
+    /// ```
+    /// # use crunch::Rect;
+    /// let parent = Rect::new(0, 0, 10, 10);
+    ///
+    /// // a rect exactly filling its parent is contained by it
+    /// assert!(parent.contains(&parent));
+    /// assert!(parent.contains(&Rect::new(5, 0, 5, 10)));
+    ///
+    /// // but not if it goes one pixel past the edge
+    /// assert!(!parent.contains(&Rect::new(5, 0, 6, 10)));
+    ///
+    /// // empty rects contain nothing, and aren't contained
+    /// assert!(!parent.contains(&Rect::new(10, 0, 0, 10)));
+    /// assert!(!parent.contains(&Rect::new(2, 2, 0, 4)));
+    /// assert!(!Rect::new(0, 0, 0, 10).contains(&Rect::new(0, 0, 0, 5)));
+    /// ```
     #[inline]
     pub const fn contains(&self, other: &Rect) -> bool {
-        other.x >= self.x
+        !self.is_empty()
+            && !other.is_empty()
+            && other.x >= self.x
             && other.y >= self.y
             && other.right() <= self.right()
             && other.bottom() <= self.bottom()
     }
 
     /// Returns true if `other` overlaps `self`.
+    ///
+    /// Since [empty](Rect::is_empty) rects cover no area, they never overlap anything,
+    /// even when they lie inside a rect.
+    ///
+    /// ```
+    /// # use crunch::Rect;
+    /// let a = Rect::new(0, 0, 10, 10);
+    ///
+    /// // rects sharing exactly an edge don't overlap
+    /// assert!(!a.overlaps(&Rect::new(10, 0, 10, 10)));
+    /// assert!(!a.overlaps(&Rect::new(0, 10, 10, 10)));
+    ///
+    /// // but they do if they cross it by one pixel
+    /// assert!(a.overlaps(&Rect::new(9, 0, 10, 10)));
+    ///
+    /// // empty rects overlap nothing
+    /// assert!(!a.overlaps(&Rect::new(5, 5, 0, 2)));
+    /// assert!(!Rect::new(5, 5, 2, 0).overlaps(&a));
+    /// ```
     #[inline]
     pub const fn overlaps(&self, other: &Rect) -> bool {
-        self.x < other.right()
+        !self.is_empty()
+            && !other.is_empty()
+            && self.x < other.right()
             && self.y < other.bottom()
             && self.right() > other.x
             && self.bottom() > other.y
     }
 
+    /// Returns the rectangle where `self` and `other` overlap, or `None` if they don't.
+    #[inline]
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        self.overlaps(other).then(|| {
+            let x = self.x.max(other.x);
+            let y = self.y.max(other.y);
+            let r = self.right().min(other.right());
+            let b = self.bottom().min(other.bottom());
+            Self::new(x, y, r - x, b - y)
+        })
+    }
+
+    /// The area of the rectangle where `self` and `other` overlap (0 if they don't).
+    ///
+    /// ```
+    /// # use crunch::Rect;
+    /// let a = Rect::new(0, 0, 10, 10);
+    ///
+    /// // partial overlap
+    /// assert_eq!(a.overlap_area(&Rect::new(5, 5, 10, 10)), 25);
+    ///
+    /// // full overlap
+    /// assert_eq!(a.overlap_area(&Rect::new(2, 2, 4, 4)), 16);
+    /// assert_eq!(a.overlap_area(&a), 100);
+    ///
+    /// // no overlap
+    /// assert_eq!(a.overlap_area(&Rect::new(10, 0, 10, 10)), 0);
+    /// ```
+    #[inline]
+    pub fn overlap_area(&self, other: &Rect) -> usize {
+        self.intersection(other).map_or(0, |r| r.area())
+    }
+
+    /// Moves and, only if it has to, shrinks the rectangle so it lies within `bounds`.
+    ///
+    /// The rectangle is shifted the least distance it takes to fit inside `bounds`,
+    /// keeping its size. Only if it is wider or taller than `bounds` is it shrunk to
+    /// match them on that axis, which places it at `bounds`' edge.
+    ///
+    /// ```
+    /// # use crunch::Rect;
+    /// let bounds = Rect::new(10, 10, 100, 50);
+    ///
+    /// // rects that fit are shifted back inside, keeping their size
+    /// assert_eq!(Rect::new(95, 20, 30, 10).clamp_into(&bounds), Rect::new(80, 20, 30, 10));
+    /// assert_eq!(Rect::new(0, 0, 5, 5).clamp_into(&bounds), Rect::new(10, 10, 5, 5));
+    ///
+    /// // rects too large are shrunk to fit
+    /// assert_eq!(Rect::new(0, 40, 200, 10).clamp_into(&bounds), Rect::new(10, 40, 100, 10));
+    /// assert_eq!(Rect::new(50, 50, 500, 500).clamp_into(&bounds), bounds);
+    ///
+    /// // rects already inside are unchanged
+    /// assert_eq!(Rect::new(20, 20, 5, 5).clamp_into(&bounds), Rect::new(20, 20, 5, 5));
+    /// ```
+    #[inline]
+    pub fn clamp_into(&self, bounds: &Rect) -> Rect {
+        let (w, h) = (self.w.min(bounds.w), self.h.min(bounds.h));
+        let x = self.x.clamp(bounds.x, bounds.right() - w);
+        let y = self.y.clamp(bounds.y, bounds.bottom() - h);
+        Self::new(x, y, w, h)
+    }
+
+    /// Splits the rectangle into four quarters: top-left, top-right, bottom-left,
+    /// and bottom-right.
+    ///
+    /// If the width or height is odd, the extra pixel goes to the right or bottom
+    /// quarters. Together, the quarters always cover the rectangle exactly.
+    ///
+    /// ```
+    /// # use crunch::Rect;
+    /// let [tl, tr, bl, br] = Rect::new(10, 10, 8, 8).quadrants();
+    /// assert_eq!(tl, Rect::new(10, 10, 4, 4));
+    /// assert_eq!(tr, Rect::new(14, 10, 4, 4));
+    /// assert_eq!(bl, Rect::new(10, 14, 4, 4));
+    /// assert_eq!(br, Rect::new(14, 14, 4, 4));
+    ///
+    /// // odd sizes give the remainder to the right and bottom
+    /// let [tl, tr, bl, br] = Rect::of_size(5, 3).quadrants();
+    /// assert_eq!(tl, Rect::new(0, 0, 2, 1));
+    /// assert_eq!(tr, Rect::new(2, 0, 3, 1));
+    /// assert_eq!(bl, Rect::new(0, 1, 2, 2));
+    /// assert_eq!(br, Rect::new(2, 1, 3, 2));
+    /// ```
+    #[inline]
+    pub const fn quadrants(&self) -> [Rect; 4] {
+        let (left_w, top_h) = (self.w / 2, self.h / 2);
+        let (right_w, bottom_h) = (self.w - left_w, self.h - top_h);
+        let (mid_x, mid_y) = (self.x + left_w, self.y + top_h);
+        [
+            Self::new(self.x, self.y, left_w, top_h),
+            Self::new(mid_x, self.y, right_w, top_h),
+            Self::new(self.x, mid_y, left_w, bottom_h),
+            Self::new(mid_x, mid_y, right_w, bottom_h),
+        ]
+    }
+
     /// The rectangle's top-left coordinates.
     #[inline]
     pub const fn top_left(&self) -> (usize, usize) {
@@ -76,3 +253,29 @@ impl Rect {
         ]
     }
 }
+
+/// Converts an `(x, y, w, h)` tuple into a `Rect`.
+///
+/// ```
+/// # use crunch::Rect;
+/// let rect = Rect::from((1, 2, 30, 40));
+/// assert_eq!(rect, Rect::new(1, 2, 30, 40));
+///
+/// // and back again
+/// let (x, y, w, h) = rect.into();
+/// assert_eq!((x, y, w, h), (1, 2, 30, 40));
+/// ```
+impl From<(usize, usize, usize, usize)> for Rect {
+    #[inline]
+    fn from((x, y, w, h): (usize, usize, usize, usize)) -> Self {
+        Self::new(x, y, w, h)
+    }
+}
+
+/// Converts a `Rect` into an `(x, y, w, h)` tuple.
+impl From<Rect> for (usize, usize, usize, usize) {
+    #[inline]
+    fn from(rect: Rect) -> Self {
+        (rect.x, rect.y, rect.w, rect.h)
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{Item, Packer, Rect, Rotation};
+
+/// A minimal interface for packing rectangles, with no generics or item data, so
+/// code can be written against it and swap between packing backends.
+///
+/// Adapters for other rectangle packing crates can implement this, and a
+/// `Packer<usize>` implements it by replacing its queued items with ones of the
+/// given sizes (each with its index as its data). Items
+/// are never rotated, unless the packer has a
+/// [rotation override](Packer::with_rotation_override), and every other
+/// setting (like the strategy or heuristic) the packer was built with applies.
+///
+/// ```
+/// # use crunch::{Packer, Rect, RectPacker};
+/// let mut backend: Box<dyn RectPacker> = Box::new(Packer::new());
+/// let rects = backend.pack(&[(8, 8), (8, 4), (32, 1), (8, 4)], Rect::of_size(16, 8));
+///
+/// // the results line up with the sizes, and the one too wide to fit is `None`
+/// assert_eq!(rects.len(), 4);
+/// assert_eq!(rects[0].map(|r| (r.w, r.h)), Some((8, 8)));
+/// assert_eq!(rects[1].map(|r| (r.w, r.h)), Some((8, 4)));
+/// assert_eq!(rects[2], None);
+/// assert_eq!(rects[3].map(|r| (r.w, r.h)), Some((8, 4)));
+/// ```
+pub trait RectPacker {
+    /// Packs rectangles of the `(w, h)` `sizes` into `bin`, returning where each one
+    /// was packed, in the same order, or `None` for those that didn't fit.
+    fn pack(&mut self, sizes: &[(usize, usize)], bin: Rect) -> Vec<Option<Rect>>;
+}
+
+impl RectPacker for Packer<usize> {
+    fn pack(&mut self, sizes: &[(usize, usize)], bin: Rect) -> Vec<Option<Rect>> {
+        self.reset();
+        let items = sizes.iter().enumerate();
+        self.extend(items.map(|(i, &(w, h))| Item::new(i, w, h, Rotation::None)));
+        let flat = self.pack_each(bin);
+        flat.into_iter()
+            .map(|slot| slot.map(|(rect, _)| rect))
+            .collect()
+    }
+}
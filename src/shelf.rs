@@ -0,0 +1,112 @@
+use crate::Rect;
+
+/// A horizontal band of the container that items are placed along, left to right.
+struct Shelf {
+    y: usize,
+    h: usize,
+    x: usize,
+}
+
+/// The open shelves of a container being packed with [`Strategy::Shelf`](crate::Strategy::Shelf).
+pub(crate) struct Shelves {
+    into_rect: Rect,
+    shelves: Vec<Shelf>,
+}
+
+impl Shelves {
+    pub(crate) const fn new() -> Self {
+        Self {
+            into_rect: Rect::new(0, 0, 0, 0),
+            shelves: Vec::new(),
+        }
+    }
+
+    /// Remove all shelves and start packing `into_rect` from the top.
+    pub(crate) fn clear(&mut self, into_rect: Rect) {
+        self.into_rect = into_rect;
+        self.shelves.clear();
+    }
+
+    /// Find the shelf a `w` x `h` item fits on with the least height to spare, where
+    /// the item would end up inside `region`.
+    fn find_best(&self, w: usize, h: usize, region: Option<&Rect>) -> Option<(usize, usize)> {
+        let right = self.into_rect.right();
+        let inside = |rect: Rect| region.map_or(true, |r| r.contains(&rect));
+        self.shelves
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| h <= s.h && w <= right - s.x)
+            .filter(|(_, s)| inside(Rect::new(s.x, s.y, w, h)))
+            .map(|(i, s)| (i, s.h - h))
+            .min_by_key(|&(_, waste)| waste)
+    }
+
+    /// The free space remaining on a shelf.
+    fn free_rect(&self, shelf: &Shelf) -> Rect {
+        Rect::new(shelf.x, shelf.y, self.into_rect.right() - shelf.x, shelf.h)
+    }
+
+    /// Place a `w` x `h` item (or `h` x `w` if `rotate` is allowed) on the best
+    /// fitting shelf, opening a new one if none of them fit. Before rotating the
+    /// item, `allow_rotation` is asked if it may be rotated into the free space.
+    ///
+    /// Shelves only ever grow rightwards and downwards, so if there is a `region`,
+    /// the item is only placed if the spot it would take anyway is inside it.
+    pub(crate) fn place<F>(
+        &mut self,
+        w: usize,
+        h: usize,
+        rotate: bool,
+        region: Option<&Rect>,
+        allow_rotation: F,
+    ) -> Option<Rect>
+    where
+        F: Fn(&Rect) -> bool,
+    {
+        let mut best = self
+            .find_best(w, h, region)
+            .map(|(i, waste)| (i, waste, w, h));
+        if rotate {
+            if let Some((i, waste)) = self.find_best(h, w, region) {
+                if best.map_or(true, |b| waste < b.1)
+                    && allow_rotation(&self.free_rect(&self.shelves[i]))
+                {
+                    best = Some((i, waste, h, w));
+                }
+            }
+        }
+
+        if let Some((i, _, w, h)) = best {
+            let shelf = &mut self.shelves[i];
+            let rect = Rect::new(shelf.x, shelf.y, w, h);
+            shelf.x += w;
+            return Some(rect);
+        }
+
+        // open a new shelf below the last one, keeping it as short as possible
+        let y = self.shelves.last().map_or(self.into_rect.y, |s| s.y + s.h);
+        let free = Rect::new(
+            self.into_rect.x,
+            y,
+            self.into_rect.w,
+            self.into_rect.bottom() - y,
+        );
+        let flatter = h > w || w > free.w;
+        let (w, h) = match rotate && flatter && h <= free.w && allow_rotation(&free) {
+            true => (h, w),
+            false => (w, h),
+        };
+        if w > free.w || h > free.h {
+            return None;
+        }
+        if region.is_some_and(|r| !r.contains(&Rect::new(free.x, y, w, h))) {
+            return None;
+        }
+        self.shelves.push(Shelf {
+            y,
+            h,
+            x: self.into_rect.x + w,
+        });
+        Some(Rect::new(self.into_rect.x, y, w, h))
+    }
+}
@@ -0,0 +1,563 @@
+use crate::index::FreeIndex;
+use crate::{Heuristic, Rect};
+use std::cmp::Ordering;
+
+/// Decides which of two equally scored free rects to pack into, see
+/// [`Packer::with_node_tiebreak`](crate::Packer::with_node_tiebreak).
+pub(crate) type Tiebreak = dyn Fn(&Rect, &Rect) -> Ordering + Send + Sync;
+
+/// The tree of free space in a container being packed with
+/// [`Strategy::Tree`](crate::Strategy::Tree).
+pub(crate) struct Tree {
+    pub(crate) nodes: Vec<Node>,
+    use_index: bool,
+    heuristic: Heuristic,
+    index: FreeIndex,
+    leaves: usize,
+    compact_at: usize,
+}
+
+/// The tree isn't compacted until it has at least this many nodes.
+const MIN_COMPACT_NODES: usize = 256;
+
+/// The tree is compacted once it has this many nodes for every leaf.
+const COMPACT_RATIO: usize = 4;
+
+impl Tree {
+    pub(crate) const fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            use_index: false,
+            heuristic: Heuristic::BestAreaFit,
+            index: FreeIndex::new(),
+            leaves: 0,
+            compact_at: MIN_COMPACT_NODES,
+        }
+    }
+
+    /// Remove all nodes, and start again with a single leaf node for `into_rect`,
+    /// with room reserved for `capacity` nodes, scoring nodes with `heuristic`.
+    pub(crate) fn reset(
+        &mut self,
+        into_rect: Rect,
+        capacity: usize,
+        use_index: bool,
+        heuristic: Heuristic,
+    ) {
+        self.use_index = use_index;
+        self.heuristic = heuristic;
+        self.nodes.clear();
+        self.nodes.reserve(capacity);
+        self.nodes.push(Node {
+            rect: into_rect,
+            is_split: false,
+            split: [0; 4],
+        });
+        self.leaves = 1;
+        self.compact_at = MIN_COMPACT_NODES;
+        if self.use_index {
+            self.index.clear();
+            self.index.insert(0, &into_rect);
+        }
+    }
+
+    /// Remove all nodes, keeping their memory.
+    pub(crate) fn clear(&mut self) {
+        self.nodes.clear();
+        self.leaves = 0;
+        self.compact_at = MIN_COMPACT_NODES;
+        if self.use_index {
+            self.index.clear();
+        }
+    }
+
+    /// Rebuild the tree without any branches that no longer lead to a leaf (because
+    /// all the space they covered has been filled), so it is faster to search.
+    ///
+    /// This doesn't change which nodes any search finds, just their indices.
+    pub(crate) fn compact(&mut self) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let mut nodes = Vec::with_capacity(self.nodes.len());
+        if self.compact_node(0, &mut nodes).is_none() {
+            // the root always stays, even if it's full
+            let mut root = self.nodes[0].clone();
+            root.split = [0; 4];
+            nodes.push(root);
+        }
+        self.nodes = nodes;
+        self.compact_at = (self.nodes.len() * 2).max(MIN_COMPACT_NODES);
+
+        // all the leaves moved, so the index has to be rebuilt
+        if self.use_index {
+            self.index.clear();
+            for (i, node) in self.nodes.iter().enumerate() {
+                if !node.is_split {
+                    self.index.insert(i, &node.rect);
+                }
+            }
+        }
+    }
+
+    /// The area of all the free space left, counting space covered by several
+    /// leaves only once.
+    pub(crate) fn free_area(&self) -> usize {
+        let leaves: Vec<&Rect> = self
+            .nodes
+            .iter()
+            .filter(|node| !node.is_split)
+            .map(|node| &node.rect)
+            .collect();
+
+        // the leaves overlap, so sweep across them in strips between their left and
+        // right edges, adding up the height each strip has covered
+        let mut edges: Vec<usize> = leaves.iter().flat_map(|r| [r.x, r.right()]).collect();
+        edges.sort_unstable();
+        edges.dedup();
+        let mut spans = Vec::with_capacity(leaves.len());
+        let mut area = 0;
+        for strip in edges.windows(2) {
+            let (left, right) = (strip[0], strip[1]);
+            spans.clear();
+            spans.extend(
+                leaves
+                    .iter()
+                    .filter(|r| r.x <= left && r.right() >= right)
+                    .map(|r| (r.y, r.bottom())),
+            );
+            spans.sort_unstable();
+            let (mut covered, mut covered_to) = (0, 0);
+            for (top, bottom) in spans.iter().copied() {
+                let top = top.max(covered_to);
+                if bottom > top {
+                    covered += bottom - top;
+                    covered_to = bottom;
+                }
+            }
+            area += covered * (right - left);
+        }
+        area
+    }
+
+    /// The rects of every free leaf node.
+    pub(crate) fn leaf_rects(&self) -> impl Iterator<Item = &Rect> {
+        self.nodes
+            .iter()
+            .filter(|node| !node.is_split)
+            .map(|node| &node.rect)
+    }
+
+    /// How many levels of nodes there are below the root.
+    pub(crate) fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut stack = match self.nodes.is_empty() {
+            true => Vec::new(),
+            false => vec![(0, 0)],
+        };
+        while let Some((node_index, node_depth)) = stack.pop() {
+            depth = depth.max(node_depth);
+            let node = &self.nodes[node_index];
+            if node.is_split {
+                let children = node.split.iter().filter(|&&i| i > 0);
+                stack.extend(children.map(|&i| (i, node_depth + 1)));
+            }
+        }
+        depth
+    }
+
+    //copy the node and all its live children into `nodes`, returning its new index,
+    //or `None` if there are no leaves left under it
+    fn compact_node(&self, node_index: usize, nodes: &mut Vec<Node>) -> Option<usize> {
+        let node = &self.nodes[node_index];
+        let new_index = nodes.len();
+        nodes.push(node.clone());
+        if !node.is_split {
+            return Some(new_index);
+        }
+        for (i, &child) in node.split.iter().enumerate() {
+            nodes[new_index].split[i] = match child > 0 {
+                true => self.compact_node(child, nodes).unwrap_or(0),
+                false => 0,
+            };
+        }
+        match nodes[new_index].split.iter().any(|&i| i > 0) {
+            true => Some(new_index),
+            false => {
+                nodes.pop();
+                None
+            }
+        }
+    }
+
+    //find the node that best fits a new rectangle of size (w, h) inside `region`,
+    //using the index (if enabled) to avoid searching the whole tree
+    #[inline]
+    fn find_best(
+        &self,
+        w: usize,
+        h: usize,
+        region: Option<&Rect>,
+        tiebreak: Option<&Tiebreak>,
+    ) -> (usize, Score) {
+        // the index only knows the full size of each leaf, so it can't search regions,
+        // and only knows how to skip past leaves that waste too much area (or break
+        // ties any way but by tree order)
+        if !self.use_index
+            || region.is_some()
+            || tiebreak.is_some()
+            || self.heuristic != Heuristic::BestAreaFit
+        {
+            return self.find_best_node(w, h, region, tiebreak, 0);
+        }
+        match self.index.find_best(&self.nodes, w, h) {
+            // if several leaves tie for the best score, the tree search decides which
+            // one to use so the results are the same as without the index
+            (_, score, true) => self.find_node_with_score(w, h, &score, 0),
+            (node_i, score, false) => (node_i, score),
+        }
+    }
+
+    //find the first node (in tree order) that fits a rect of size (w, h) with `score`
+    fn find_node_with_score(
+        &self,
+        w: usize,
+        h: usize,
+        score: &Score,
+        node_index: usize,
+    ) -> (usize, Score) {
+        let node = &self.nodes[node_index];
+        if w <= node.rect.w && h <= node.rect.h {
+            if node.is_split {
+                for &child in node.split.iter().filter(|&&i| i > 0) {
+                    let found = self.find_node_with_score(w, h, score, child);
+                    if found.0 != usize::MAX {
+                        return found;
+                    }
+                }
+            } else if !score.better_than(&Score::new(&node.rect, w, h)) {
+                return (node_index, *score);
+            }
+        }
+        (usize::MAX, Score::worst())
+    }
+
+    //find the node that best fits a new rectangle of size (w, h) inside `region`
+    #[inline]
+    fn find_best_node(
+        &self,
+        w: usize,
+        h: usize,
+        region: Option<&Rect>,
+        tiebreak: Option<&Tiebreak>,
+        node_index: usize,
+    ) -> (usize, Score) {
+        let node = &self.nodes[node_index];
+        let rect = match Self::clip(&node.rect, region) {
+            Some(rect) => rect,
+            None => return (usize::MAX, Score::worst()),
+        };
+
+        // check if this node's branch could potentially hold the new rect
+        if w <= rect.w && h <= rect.h {
+            // check if the node is a branch or a leaf node
+            if node.is_split {
+                // for split nodes, recursively search each branch and find the best node
+                let mut best = (usize::MAX, Score::worst());
+                for &child in node.split.iter().filter(|&&i| i > 0) {
+                    let (i, s) = self.find_best_node(w, h, region, tiebreak, child);
+                    if s.better_than(&best.1) || self.wins_tie(i, &s, &best, region, tiebreak) {
+                        best = (i, s);
+
+                        // nothing can beat a perfect fit, and ties go to the first found
+                        // (unless there's a tiebreak to decide them)
+                        if s.is_perfect() && tiebreak.is_none() {
+                            break;
+                        }
+                    }
+                }
+                best
+            } else {
+                (
+                    node_index,
+                    Score::with_heuristic(self.heuristic, &rect, w, h),
+                )
+            }
+        } else {
+            (usize::MAX, Score::worst())
+        }
+    }
+
+    //the part of `rect` that lies inside `region` (all of it if there is no region)
+    #[inline]
+    fn clip(rect: &Rect, region: Option<&Rect>) -> Option<Rect> {
+        match region {
+            Some(region) => rect.intersection(region),
+            None => Some(*rect),
+        }
+    }
+
+    //returns true if any leaf node contains the supplied rect
+    #[inline]
+    fn leaf_contains_rect(&self, rect: &Rect, node_index: usize) -> bool {
+        let node = &self.nodes[node_index];
+        match node.rect.contains(rect) {
+            false => false,
+            true => {
+                !node.is_split
+                    || node
+                        .split
+                        .iter()
+                        .any(|&i| i > 0 && self.leaf_contains_rect(rect, i))
+            }
+        }
+    }
+
+    //true if node `i` ties with the `best` node found so far and `tiebreak` prefers it
+    fn wins_tie(
+        &self,
+        i: usize,
+        score: &Score,
+        best: &(usize, Score),
+        region: Option<&Rect>,
+        tiebreak: Option<&Tiebreak>,
+    ) -> bool {
+        let tiebreak = match tiebreak {
+            Some(tiebreak) if i != usize::MAX && best.0 != usize::MAX => tiebreak,
+            _ => return false,
+        };
+        if best.1.better_than(score) {
+            return false;
+        }
+        let rect = Self::clip(&self.nodes[i].rect, region);
+        let best_rect = Self::clip(&self.nodes[best.0].rect, region);
+        match rect.zip(best_rect) {
+            Some((rect, best_rect)) => tiebreak(&rect, &best_rect) == Ordering::Less,
+            None => false,
+        }
+    }
+
+    //split all nodes that overlap with this rectangle
+    #[inline]
+    pub(crate) fn split_tree(&mut self, rect: &Rect, node_index: usize) {
+        //if the rectangle overlaps with this branch of the tree
+        if self.nodes[node_index].rect.overlaps(rect) {
+            //if the node is already split, recursively split into its child nodes
+            if self.nodes[node_index].is_split {
+                let split = self.nodes[node_index].split;
+                for i in split.iter().cloned().filter(|&i| i > 0) {
+                    self.split_tree(rect, i);
+                }
+            } else {
+                //split the rect into 0-4 sub-rects and make a new node out of each
+                self.nodes[node_index].is_split = true;
+                self.leaves -= 1;
+                if self.use_index {
+                    self.index.remove(node_index);
+                }
+                let rects = self.nodes[node_index].rect.split(rect);
+                for (i, rect) in rects.iter().enumerate() {
+                    if let Some(r) = rect {
+                        //only add the child rect if no other leaf node contains it
+                        if !self.leaf_contains_rect(r, 0) {
+                            trace!("split node {} on {:?} into {:?}", node_index, rect, r);
+                            self.nodes[node_index].split[i] = self.nodes.len();
+                            if self.use_index {
+                                self.index.insert(self.nodes.len(), r);
+                            }
+                            self.nodes.push(Node {
+                                rect: *r,
+                                is_split: false,
+                                split: [0; 4],
+                            });
+                            self.leaves += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    //find the best node to pack a (w, h) item in, or (h, w) if it can `rotate` and
+    //`allow_rotation` allows it into the node, and split the tree on it, returning the
+    //rect the item was packed into (which must lie inside `region`, if there is one)
+    pub(crate) fn place<F>(
+        &mut self,
+        w: usize,
+        h: usize,
+        rotate: bool,
+        region: Option<&Rect>,
+        tiebreak: Option<&Tiebreak>,
+        allow_rotation: F,
+    ) -> Option<Rect>
+    where
+        F: Fn(&Rect) -> bool,
+    {
+        // if the item is rotated 90º, pack_w and pack_h will be swapped
+        let (mut pack_w, mut pack_h) = (w, h);
+        let (mut node_i, mut score) = self.find_best(w, h, region, tiebreak);
+        if rotate {
+            let (i, s) = self.find_best(h, w, region, tiebreak);
+            if s.better_than(&score)
+                && Self::clip(&self.nodes[i].rect, region).is_some_and(|r| allow_rotation(&r))
+            {
+                node_i = i;
+                score = s;
+                pack_w = h;
+                pack_h = w;
+            }
+        }
+
+        if node_i == usize::MAX {
+            return None;
+        }
+
+        // get the final rectangle where the item will be packed
+        let (node_x, node_y) = Self::clip(&self.nodes[node_i].rect, region)?.top_left();
+        let rect = Rect::new(node_x, node_y, pack_w, pack_h);
+        trace!(
+            "placing {}x{} into node {} {:?} at {:?} ({:?}, rotated: {})",
+            w,
+            h,
+            node_i,
+            self.nodes[node_i].rect,
+            rect,
+            score,
+            pack_w != w
+        );
+
+        // split the tree on the new item's rect to create new packing branches
+        self.split_tree(&rect, 0);
+
+        // once most of the nodes are dead branches, drop them
+        if self.nodes.len() >= self.compact_at && self.nodes.len() > self.leaves * COMPACT_RATIO {
+            self.compact();
+        }
+        Some(rect)
+    }
+}
+
+/// A branch of the packing tree, `split` are indices that point to other nodes.
+#[derive(Clone)]
+pub(crate) struct Node {
+    pub(crate) rect: Rect,
+    is_split: bool,
+    split: [usize; 4],
+}
+
+/// The packer's way of scoring how well a rect fits into another rect.
+///
+/// Scores are ordered so that a _lower_ score is a better fit: first by how much
+/// area is left over, then by how much is left over on the shorter side.
+///
+/// (When packing with a [`Heuristic`] other than [`Heuristic::BestAreaFit`], the
+/// two measures are that heuristic's instead.)
+///
+/// ```
+/// # use crunch::{Rect, Score};
+/// // fitting 4x4 into 10x4 or 5x8 leaves the same area, but 10x4 fits its short side exactly
+/// let snug = Score::new(&Rect::of_size(10, 4), 4, 4);
+/// let loose = Score::new(&Rect::of_size(5, 8), 4, 4);
+/// assert_eq!(snug.area_fit, loose.area_fit);
+/// assert!(snug < loose);
+///
+/// // wasting less area always wins, whatever the short side
+/// let wide = Score::new(&Rect::of_size(16, 4), 4, 4);
+/// let square = Score::new(&Rect::of_size(5, 5), 4, 4);
+/// assert!(square < wide);
+///
+/// // every possible fit is better than the worst score
+/// assert!(wide < Score::worst());
+/// assert!(Score::worst() == Score::worst());
+///
+/// let mut scores = vec![wide, Score::worst(), loose, snug, square];
+/// scores.sort();
+/// assert_eq!(scores, [square, snug, loose, wide, Score::worst()]);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Score {
+    /// The area left over after fitting.
+    pub area_fit: usize,
+
+    /// The space left over on whichever side had less left over.
+    pub short_fit: usize,
+}
+
+impl Score {
+    /// Score how well a rect of size `w` x `h` fits into `rect`.
+    ///
+    /// The size must fit into `rect`, or this will panic.
+    #[inline]
+    pub fn new(rect: &Rect, w: usize, h: usize) -> Self {
+        let extra_x = rect.w - w;
+        let extra_y = rect.h - h;
+        Self {
+            area_fit: rect.area() - w.saturating_mul(h),
+            short_fit: extra_x.min(extra_y),
+        }
+    }
+
+    /// Score how well a rect of size `w` x `h` fits into `rect` using `heuristic`,
+    /// which decides what the two measures of the score are.
+    #[inline]
+    pub(crate) fn with_heuristic(heuristic: Heuristic, rect: &Rect, w: usize, h: usize) -> Self {
+        let (extra_x, extra_y) = (rect.w - w, rect.h - h);
+        let (area_fit, short_fit) = match heuristic {
+            Heuristic::BestAreaFit => return Self::new(rect, w, h),
+            Heuristic::BestShortSideFit => (extra_x.min(extra_y), extra_x.max(extra_y)),
+            Heuristic::BestLongSideFit => (extra_x.max(extra_y), extra_x.min(extra_y)),
+            Heuristic::TopLeft => (rect.y + h, rect.x),
+            Heuristic::LegacyV1 => return Self::legacy_v1(rect, w, h),
+        };
+        Self {
+            area_fit,
+            short_fit,
+        }
+    }
+
+    //the `BestAreaFit` score from crunch 0.5, frozen for `Heuristic::LegacyV1`
+    #[inline]
+    fn legacy_v1(rect: &Rect, w: usize, h: usize) -> Self {
+        let (extra_x, extra_y) = (rect.w - w, rect.h - h);
+        Self {
+            area_fit: rect.area() - w.saturating_mul(h),
+            short_fit: extra_x.min(extra_y),
+        }
+    }
+
+    /// The worst possible packing score.
+    #[inline]
+    pub const fn worst() -> Self {
+        Self {
+            area_fit: usize::MAX,
+            short_fit: usize::MAX,
+        }
+    }
+
+    /// Returns `true` if nothing is left over at all, so no other fit can be better.
+    ///
+    /// ```
+    /// # use crunch::{Rect, Score};
+    /// assert!(Score::new(&Rect::of_size(4, 8), 4, 8).is_perfect());
+    /// assert!(!Score::new(&Rect::of_size(4, 9), 4, 8).is_perfect());
+    ///
+    /// // an item that exactly fills the space left over goes right into it
+    /// # use crunch::{Item, pack};
+    /// let items = [Item::no_rotation('A', 8, 8), Item::no_rotation('B', 4, 8)];
+    /// let packed = pack(Rect::of_size(12, 8), items).unwrap();
+    /// assert_eq!(packed[1].rect, Rect::new(8, 0, 4, 8));
+    /// ```
+    #[inline]
+    pub const fn is_perfect(&self) -> bool {
+        self.area_fit == 0 && self.short_fit == 0
+    }
+
+    /// Returns `true` if this score is better than `other`.
+    ///
+    /// This is the same as `self < other`.
+    #[inline]
+    pub const fn better_than(&self, other: &Score) -> bool {
+        self.area_fit < other.area_fit
+            || (self.area_fit == other.area_fit && self.short_fit < other.short_fit)
+    }
+}